@@ -0,0 +1,27 @@
+//! Records the rustc version this driver is built (and therefore linked)
+//! against, for the `header` block of the JSON output.
+
+use std::process::Command;
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let output = Command::new(&rustc)
+        .arg("-vV")
+        .output()
+        .unwrap_or_else(|e| panic!("failed to run `{rustc} -vV`: {e}"));
+    let info = String::from_utf8(output.stdout).expect("rustc -vV output is not UTF-8");
+
+    let field = |name: &str| {
+        info.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+            .unwrap_or("unknown")
+            .to_string()
+    };
+    let version = info.lines().next().unwrap_or("unknown").to_string();
+    let commit = field("commit-hash");
+
+    println!("cargo:rustc-env=SMIR_RUSTC_VERSION={version}");
+    println!("cargo:rustc-env=SMIR_RUSTC_COMMIT={commit}");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rerun-if-changed=rust-toolchain.toml");
+}
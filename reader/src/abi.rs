@@ -0,0 +1,166 @@
+//! Mirrors of `stable_mir::abi`.
+
+use std::num::NonZero;
+use std::ops::RangeInclusive;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::target::MachineSize as Size;
+use crate::ty::{Align, Ty, VariantIdx};
+use crate::Opaque;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct LayoutShape {
+    pub fields: FieldsShape,
+    pub variants: VariantsShape,
+    pub abi: ValueAbi,
+    pub abi_align: Align,
+    pub size: Size,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum FieldsShape {
+    Primitive,
+    Union(NonZero<usize>),
+    Array { stride: Size, count: u64 },
+    Arbitrary { offsets: Vec<Size> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum VariantsShape {
+    Single {
+        index: VariantIdx,
+    },
+    Multiple {
+        tag: Scalar,
+        tag_encoding: TagEncoding,
+        tag_field: usize,
+        variants: Vec<LayoutShape>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum TagEncoding {
+    Direct,
+    Niche {
+        untagged_variant: VariantIdx,
+        niche_variants: RangeInclusive<VariantIdx>,
+        niche_start: u128,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ValueAbi {
+    Uninhabited,
+    Scalar(Scalar),
+    ScalarPair(Scalar, Scalar),
+    Vector { element: Scalar, count: u64 },
+    Aggregate { sized: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum Scalar {
+    Initialized {
+        value: Primitive,
+        valid_range: WrappingRange,
+    },
+    Union {
+        value: Primitive,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum Primitive {
+    Int { length: IntegerLength, signed: bool },
+    Float { length: FloatLength },
+    Pointer(AddressSpace),
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub enum IntegerLength {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub enum FloatLength {
+    F16,
+    F32,
+    F64,
+    F128,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct AddressSpace(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct WrappingRange {
+    pub start: u128,
+    pub end: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct FnAbi {
+    pub args: Vec<ArgAbi>,
+    pub ret: ArgAbi,
+    pub fixed_count: u32,
+    pub conv: CallConvention,
+    pub c_variadic: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct ArgAbi {
+    pub ty: Ty,
+    pub layout: LayoutShape,
+    pub mode: PassMode,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum PassMode {
+    Ignore,
+    Direct(Opaque),
+    Pair(Opaque, Opaque),
+    Cast {
+        pad_i32: bool,
+        cast: Opaque,
+    },
+    Indirect {
+        attrs: Opaque,
+        meta_attrs: Opaque,
+        on_stack: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum CallConvention {
+    C,
+    Rust,
+    Cold,
+    PreserveMost,
+    PreserveAll,
+    ArmAapcs,
+    CCmseNonSecureCall,
+    CCmseNonSecureEntry,
+    Msp430Intr,
+    PtxKernel,
+    X86Fastcall,
+    X86Intr,
+    X86Stdcall,
+    X86ThisCall,
+    X86VectorCall,
+    X86_64SysV,
+    X86_64Win64,
+    AvrInterrupt,
+    AvrNonBlockingInterrupt,
+    RiscvInterrupt,
+}
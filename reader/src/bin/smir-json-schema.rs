@@ -0,0 +1,6 @@
+//! Print the JSON Schema for `*.smir.json` files to stdout.
+
+fn main() {
+    let schema = stable_mir_json_reader::json_schema();
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
@@ -0,0 +1,73 @@
+//! Link the `*.smir.json` outputs of several crates into one program.
+//!
+//! Usage: `smir-link [-o OUT] MAIN [INPUT...]`. Inputs may be `*.smir.json`,
+//! `*.smir.cbor` or split output directories, all written with stable IDs;
+//! the first is the main crate. The linked program goes to `OUT` (JSON, or
+//! CBOR if it ends in `.cbor`), or to stdout; symbols that no input defines
+//! are listed on stderr.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use stable_mir_json_reader::{link, load, load_split, save, to_string, Error, SmirJson};
+
+const USAGE: &str = "Usage: smir-link [-o OUT] MAIN [INPUT...]";
+
+fn load_input(path: &Path) -> Result<SmirJson, Error> {
+    if path.is_dir() {
+        load_split(path)
+    } else {
+        load(path)
+    }
+}
+
+fn run(out: Option<PathBuf>, inputs: Vec<PathBuf>) -> Result<(), String> {
+    let inputs = inputs
+        .iter()
+        .map(|path| load_input(path).map_err(|e| format!("{}: {e}", path.display())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let linked = link(inputs).map_err(|e| e.to_string())?;
+    for symbol in &linked.unresolved {
+        eprintln!("unresolved symbol: {symbol}");
+    }
+    match out {
+        Some(out) => save(&out, &linked.smir).map_err(|e| format!("{}: {e}", out.display())),
+        None => {
+            println!("{}", to_string(&linked.smir).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut out = None;
+    let mut inputs = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => match args.next() {
+                Some(path) => out = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("`-o` requires a value\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if inputs.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    match run(out, inputs) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("smir-link: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Reader for the `*.smir.json` (and `*.smir.cbor`) files produced by
+//! `stable_mir_json`.
+//!
+//! The producer serializes compiler-owned types (`stable_mir`'s MIR, types,
+//! layouts) which only implement `Serialize`, and only against the `serde`
+//! build vendored into the rustc sysroot. This crate mirrors the whole output
+//! schema with plain `Serialize + Deserialize` types and no `rustc_private`
+//! dependency, so downstream tools can depend on it from any toolchain.
+//!
+//! Module layout follows `stable_mir` ([`ty`], [`mir`], [`abi`], [`target`])
+//! so paths carry over; the top-level output types live in [`schema`] and
+//! are re-exported here. Every type serializes back to exactly the JSON it
+//! was read from. The CBOR output carries the same data under the same field
+//! names and enum tagging, so it reads into the same types. [`link`] merges
+//! the outputs of several crates into one program (see the `smir-link`
+//! binary).
+//!
+//! ```no_run
+//! let smir = stable_mir_json_reader::load("main.smir.json")?;
+//! println!("{} items", smir.items.len());
+//! # Ok::<(), stable_mir_json_reader::Error>(())
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Serialize};
+
+pub mod abi;
+pub mod link;
+pub mod mir;
+pub mod schema;
+pub mod target;
+pub mod ty;
+
+pub use link::{link, LinkError, Linked};
+pub use schema::{
+    AdtRepr, AllocInfo, CallEdge, CallKind, DecodedValue, EnumLayout, EnumTag, FnSignature,
+    FnSymType, GenericItem, InstanceKindKey, InstanceKindTag, Item, ItemEntry, ItemFilter,
+    LinkMapCandidate, LinkMapConflict, LinkMapKey, MonoItemKind, OutputFlags, ReifyReason,
+    SmirJson, SmirJsonDebugInfo, SmirJsonHeader, SplitIndex, TypeMetadata, UpstreamBodies,
+    VTableEntry, VTableFn, VTableInfo, VariantLayout, SCHEMA_VERSION,
+};
+
+pub type Symbol = String;
+
+/// Interned definition handle.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct DefId(pub usize);
+
+/// A compiler-internal value exposed only through its debug representation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct Opaque(pub String);
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct CrateItem(pub DefId);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ItemKind {
+    Fn,
+    Static,
+    Const,
+    Ctor(CtorKind),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum CtorKind {
+    Const,
+    Fn,
+}
+
+/// Errors from reading or writing a `*.smir.json` or `*.smir.cbor` file.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    CborDecode(ciborium::de::Error<io::Error>),
+    CborEncode(ciborium::ser::Error<io::Error>),
+    /// The file was written with a schema revision this crate does not read.
+    UnsupportedSchema {
+        found: u32,
+        expected: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Json(e) => write!(f, "malformed smir.json: {e}"),
+            Error::CborDecode(e) => write!(f, "malformed smir.cbor: {e}"),
+            Error::CborEncode(e) => write!(f, "cannot encode smir.cbor: {e}"),
+            Error::UnsupportedSchema { found, expected } => write!(
+                f,
+                "unsupported schema version {found} (this reader supports {expected})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::CborDecode(e) => Some(e),
+            Error::CborEncode(e) => Some(e),
+            Error::UnsupportedSchema { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<ciborium::de::Error<io::Error>> for Error {
+    fn from(e: ciborium::de::Error<io::Error>) -> Self {
+        Error::CborDecode(e)
+    }
+}
+
+impl From<ciborium::ser::Error<io::Error>> for Error {
+    fn from(e: ciborium::ser::Error<io::Error>) -> Self {
+        Error::CborEncode(e)
+    }
+}
+
+fn is_cbor(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "cbor")
+}
+
+/// Read a `*.smir.json` file, or a `*.smir.cbor` file if the path ends in
+/// `.cbor`.
+pub fn load(path: impl AsRef<Path>) -> Result<SmirJson, Error> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    if is_cbor(path) {
+        let mut cbor = Vec::new();
+        reader.read_to_end(&mut cbor)?;
+        from_cbor_slice(&cbor)
+    } else {
+        let mut json = String::new();
+        reader.read_to_string(&mut json)?;
+        from_str(&json)
+    }
+}
+
+/// Only the schema version, which every revision of the header carries, so
+/// that files of other revisions are recognised before their shape matters.
+#[derive(Deserialize)]
+struct VersionHeader {
+    schema_version: u32,
+}
+
+/// Check the schema version of a parsed document or, if parsing failed, of
+/// its header alone, so that files of another revision fail with
+/// [`Error::UnsupportedSchema`] rather than wherever their shape differs.
+fn check_version<T>(
+    parsed: Result<T, Error>,
+    header: impl FnOnce(&T) -> &SmirJsonHeader,
+    read_version: impl FnOnce() -> Result<VersionHeader, Error>,
+) -> Result<T, Error> {
+    let found = match &parsed {
+        Ok(value) => header(value).schema_version,
+        Err(_) => match read_version() {
+            Ok(version) => version.schema_version,
+            Err(_) => return parsed,
+        },
+    };
+    if found != SCHEMA_VERSION {
+        return Err(Error::UnsupportedSchema {
+            found,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    parsed
+}
+
+/// Deserialize the `header` entry, which the producer writes first, from the
+/// start of JSON content without reading the entries after it.
+fn json_header<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    let not_first = || Error::Json(de::Error::custom("expected `header` as the first entry"));
+    let rest = json.trim_start().strip_prefix('{').ok_or_else(not_first)?;
+    let mut keys = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+    if keys.next().transpose()?.as_deref() != Some("header") {
+        return Err(not_first());
+    }
+    let rest = rest[keys.byte_offset()..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or_else(not_first)?;
+    let mut values = serde_json::Deserializer::from_str(rest).into_iter::<T>();
+    values.next().transpose()?.ok_or_else(not_first)
+}
+
+/// Parse `*.smir.json` content.
+///
+/// Content of another schema version fails with [`Error::UnsupportedSchema`]
+/// rather than a parse error deep inside.
+pub fn from_str(json: &str) -> Result<SmirJson, Error> {
+    check_version(
+        serde_json::from_str(json).map_err(Error::from),
+        |smir: &SmirJson| &smir.header,
+        || json_header(json),
+    )
+}
+
+/// Parse `*.smir.cbor` content, checking the schema version as [`from_str`]
+/// does.
+pub fn from_cbor_slice(cbor: &[u8]) -> Result<SmirJson, Error> {
+    check_version(
+        ciborium::from_reader(cbor).map_err(Error::from),
+        |smir: &SmirJson| &smir.header,
+        || cbor_header(cbor),
+    )
+}
+
+/// The `header` entry, which the producer writes first, of a document whose
+/// other entries are not read.
+struct HeaderPrefix<T>(T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for HeaderPrefix<T> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FirstEntry<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for FirstEntry<T> {
+            type Value = HeaderPrefix<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map starting with `header`")
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                match map.next_key::<String>()? {
+                    Some(key) if key == "header" => Ok(HeaderPrefix(map.next_value()?)),
+                    _ => Err(de::Error::custom("expected `header` as the first entry")),
+                }
+            }
+        }
+
+        deserializer.deserialize_map(FirstEntry(PhantomData))
+    }
+}
+
+/// Deserialize the `header` entry from the start of CBOR content. Unlike
+/// `serde_json`, `ciborium` does not require a map to be read to its end,
+/// so decoding stops after the header.
+fn cbor_header<T: DeserializeOwned>(cbor: &[u8]) -> Result<T, Error> {
+    Ok(ciborium::from_reader::<HeaderPrefix<T>, _>(cbor)?.0)
+}
+
+/// Parse only the header of `*.smir.json` content, without checking it. The
+/// rest of the content is not read.
+pub fn read_header(json: &str) -> Result<SmirJsonHeader, Error> {
+    json_header(json)
+}
+
+/// Parse only the header of `*.smir.cbor` content, without checking it. The
+/// rest of the content is not read.
+pub fn read_cbor_header(cbor: &[u8]) -> Result<SmirJsonHeader, Error> {
+    cbor_header(cbor)
+}
+
+/// Read the `index.json` of a split output directory (`--smir-format=split`),
+/// checking its schema version as [`from_str`] does.
+pub fn load_split_index(dir: impl AsRef<Path>) -> Result<SplitIndex, Error> {
+    let mut json = String::new();
+    BufReader::new(File::open(dir.as_ref().join("index.json"))?).read_to_string(&mut json)?;
+    check_version(
+        serde_json::from_str(&json).map_err(Error::from),
+        |index: &SplitIndex| &index.header,
+        || json_header(&json),
+    )
+}
+
+/// Read one item file of a split output directory.
+pub fn load_split_item(dir: impl AsRef<Path>, entry: &ItemEntry) -> Result<Item, Error> {
+    let path = dir.as_ref().join("items").join(&entry.file);
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+/// Read a whole split output directory into the value the single-file
+/// output holds.
+pub fn load_split(dir: impl AsRef<Path>) -> Result<SmirJson, Error> {
+    let dir = dir.as_ref();
+    let index = load_split_index(dir)?;
+    let items = index
+        .items
+        .iter()
+        .map(|entry| load_split_item(dir, entry))
+        .collect::<Result<_, _>>()?;
+    Ok(index.into_smir_json(items))
+}
+
+/// JSON Schema (draft 2020-12) describing the whole `*.smir.json` format,
+/// generated from the types in this crate.
+pub fn json_schema() -> schemars::Schema {
+    schemars::schema_for!(SmirJson)
+}
+
+/// Serialize in the producer's (compact) format.
+pub fn to_string(smir: &SmirJson) -> Result<String, Error> {
+    Ok(serde_json::to_string(smir)?)
+}
+
+/// Serialize as CBOR, in the same layout as the producer's `*.smir.cbor`.
+pub fn to_cbor_vec(smir: &SmirJson) -> Result<Vec<u8>, Error> {
+    let mut cbor = Vec::new();
+    ciborium::into_writer(smir, &mut cbor)?;
+    Ok(cbor)
+}
+
+/// Write a `*.smir.json` file in the producer's (compact) format, or a
+/// `*.smir.cbor` file if the path ends in `.cbor`.
+pub fn save(path: impl AsRef<Path>, smir: &SmirJson) -> Result<(), Error> {
+    let path = path.as_ref();
+    let mut writer = BufWriter::new(File::create(path)?);
+    if is_cbor(path) {
+        ciborium::into_writer(smir, &mut writer)?;
+    } else {
+        serde_json::to_writer(&mut writer, smir)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! Linking the outputs of several crates into one program.
+//!
+//! Running the driver over a workspace gives one `*.smir.json` per crate,
+//! whose `functions` refer by symbol to items defined in other crates.
+//! [`link`] merges such outputs into a single [`SmirJson`]:
+//!
+//! - `types`, `allocs`, `spans` and the debug type table are merged by ID.
+//!   This needs content-derived IDs (`STABLE_IDS`), under which equal IDs
+//!   mean equal content; an ID with different content in two inputs is a
+//!   [`LinkError::Conflict`].
+//! - `items` are merged by symbol name. A body added by `--smir-upstream`
+//!   gives way to the item of the crate that defines it.
+//! - `functions`, `uneval_consts`, `generic_items` and the debug source
+//!   table are merged by key, keeping the first input's entry: an instance
+//!   monomorphized in several crates has one symbol per crate, and calls to
+//!   it all resolve to the first. `vtables` are likewise merged by type and
+//!   trait. The debug link map conflicts of every input are kept.
+//! - `calls` are kept for the items that end up in the result, so the edges
+//!   of an upstream copy that gave way are dropped along with it.
+//! - `NormalSym` entries whose symbol no item defines are listed in
+//!   [`Linked::unresolved`].
+//!
+//! The first input is the main crate: the result keeps its header, `name`
+//! and `crate_id`. Entries keep their input order, first input first.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::schema::{CallEdge, FnSymType, Item, SmirJson, SmirJsonDebugInfo, VTableInfo};
+
+/// The linked program and the symbols it calls without defining.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Linked {
+    pub smir: SmirJson,
+    /// Symbols of `NormalSym` entries that no item defines, sorted.
+    pub unresolved: Vec<String>,
+}
+
+/// Inputs that cannot be linked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkError {
+    NoInputs,
+    /// The named input was not written with content-derived IDs.
+    NotStableIds(String),
+    /// The named input differs from the first in a way that makes its IDs or
+    /// keys incomparable.
+    Incompatible {
+        name: String,
+        what: &'static str,
+    },
+    /// The named input has different content than an earlier one under the
+    /// same ID.
+    Conflict {
+        name: String,
+        table: &'static str,
+        id: usize,
+    },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::NoInputs => write!(f, "nothing to link"),
+            LinkError::NotStableIds(name) => write!(
+                f,
+                "`{name}` was not written with stable IDs (STABLE_IDS or --smir-stable-ids)"
+            ),
+            LinkError::Incompatible { name, what } => {
+                write!(f, "`{name}` has a different {what} than the main crate")
+            }
+            LinkError::Conflict { name, table, id } => {
+                write!(f, "`{name}` has different content for {table} ID {id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Entries of one table, merged by key.
+struct Table<K, V> {
+    entries: Vec<V>,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq, V: PartialEq> Table<K, V> {
+    fn new() -> Self {
+        Table {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Add `entry` unless its key is already present; returns whether the
+    /// present entry differs.
+    fn insert(&mut self, key: K, entry: V) -> bool {
+        match self.index.entry(key) {
+            Entry::Occupied(present) => self.entries[*present.get()] != entry,
+            Entry::Vacant(vacant) => {
+                vacant.insert(self.entries.len());
+                self.entries.push(entry);
+                false
+            }
+        }
+    }
+
+    /// Add the entries of `from` whose key is new.
+    fn merge_first(&mut self, from: Vec<V>, key: impl Fn(&V) -> K) {
+        for entry in from {
+            self.insert(key(&entry), entry);
+        }
+    }
+}
+
+impl<V: PartialEq> Table<usize, V> {
+    /// Add the entries of `from` under content-derived IDs, which must not
+    /// conflict with present ones.
+    fn merge_ids(
+        &mut self,
+        from: Vec<V>,
+        id: impl Fn(&V) -> usize,
+        name: &str,
+        table: &'static str,
+    ) -> Result<(), LinkError> {
+        for entry in from {
+            let id = id(&entry);
+            if self.insert(id, entry) {
+                return Err(LinkError::Conflict {
+                    name: name.to_string(),
+                    table,
+                    id,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that `smir` can be linked with `main`.
+fn check_compatible(main: &SmirJson, smir: &SmirJson) -> Result<(), LinkError> {
+    if !smir.header.flags.stable_ids {
+        return Err(LinkError::NotStableIds(smir.name.clone()));
+    }
+    let incompatible = |what| {
+        Err(LinkError::Incompatible {
+            name: smir.name.clone(),
+            what,
+        })
+    };
+    if smir.header.rustc_commit != main.header.rustc_commit {
+        return incompatible("rustc commit");
+    }
+    if smir.machine != main.machine {
+        return incompatible("target machine");
+    }
+    if smir.header.flags.link_inst != main.header.flags.link_inst {
+        return incompatible("link map key (LINK_INST)");
+    }
+    if smir.header.flags.debug != main.header.flags.debug {
+        return incompatible("debug setting (DEBUG)");
+    }
+    Ok(())
+}
+
+/// Link the outputs of several crates, the main crate first.
+pub fn link(inputs: Vec<SmirJson>) -> Result<Linked, LinkError> {
+    let Some(main) = inputs.first() else {
+        return Err(LinkError::NoInputs);
+    };
+    for smir in &inputs {
+        check_compatible(main, smir)?;
+    }
+
+    let mut allocs = Table::new();
+    let mut functions = Table::new();
+    let mut vtables = Vec::<VTableInfo>::new();
+    // Call edges with the input they come from, and the input of each item.
+    let mut calls = Vec::<(usize, CallEdge)>::new();
+    let mut item_inputs = HashMap::<String, usize>::new();
+    let mut uneval_consts = Table::new();
+    let mut items = Table::<String, Item>::new();
+    let mut generic_items = Table::new();
+    let mut types = Table::new();
+    let mut spans = Table::new();
+    let mut fn_sources = Table::new();
+    let mut link_conflicts = Vec::new();
+    let mut debug_types = Table::new();
+    let mut foreign_modules = Table::new();
+    let mut linked = None;
+
+    for (input, smir) in inputs.into_iter().enumerate() {
+        let name = smir.name.as_str();
+        allocs.merge_ids(smir.allocs, |a| a.alloc_id.0, name, "alloc")?;
+        types.merge_ids(smir.types, |(ty, _)| ty.0, name, "type")?;
+        spans.merge_ids(smir.spans, |(span, _)| span.0, name, "span")?;
+        functions.merge_first(smir.functions, |(key, ..)| key.clone());
+        for vtable in smir.vtables {
+            if !vtables
+                .iter()
+                .any(|v| v.ty == vtable.ty && v.trait_ref == vtable.trait_ref)
+            {
+                vtables.push(vtable);
+            }
+        }
+        uneval_consts.merge_first(smir.uneval_consts, |(def, _)| *def);
+        generic_items.merge_first(smir.generic_items, |item| item.id);
+        for item in smir.items {
+            match items.index.get(&item.symbol_name) {
+                // The defining crate's item replaces an upstream copy.
+                Some(&i) => {
+                    if items.entries[i].external_crate.is_some() && item.external_crate.is_none() {
+                        item_inputs.insert(item.symbol_name.clone(), input);
+                        items.entries[i] = item;
+                    }
+                }
+                None => {
+                    item_inputs.insert(item.symbol_name.clone(), input);
+                    items.insert(item.symbol_name.clone(), item);
+                }
+            }
+        }
+        calls.extend(smir.calls.into_iter().map(|call| (input, call)));
+        if let Some(debug) = smir.debug {
+            debug_types.merge_ids(debug.types, |(ty, _)| ty.0, name, "debug type")?;
+            fn_sources.merge_first(debug.fn_sources, |(key, _)| key.clone());
+            link_conflicts.extend(debug.link_conflicts);
+            foreign_modules.merge_first(debug.foreign_modules, |(krate, _)| krate.clone());
+        }
+        linked.get_or_insert(SmirJson {
+            allocs: Vec::new(),
+            functions: Vec::new(),
+            vtables: Vec::new(),
+            calls: Vec::new(),
+            uneval_consts: Vec::new(),
+            items: Vec::new(),
+            generic_items: Vec::new(),
+            types: Vec::new(),
+            spans: Vec::new(),
+            debug: None,
+            ..smir
+        });
+    }
+
+    let mut smir = linked.expect("at least one input");
+    let debug = smir.header.flags.debug.then_some(SmirJsonDebugInfo {
+        fn_sources: fn_sources.entries,
+        link_conflicts,
+        types: debug_types.entries,
+        foreign_modules: foreign_modules.entries,
+    });
+    let defined = items
+        .entries
+        .iter()
+        .map(|item| item.symbol_name.as_str())
+        .collect::<HashSet<_>>();
+    let mut unresolved = functions
+        .entries
+        .iter()
+        .filter_map(|(_, sym, _)| match sym {
+            FnSymType::NormalSym(name) if !defined.contains(name.as_str()) => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    unresolved.sort();
+    unresolved.dedup();
+
+    smir.allocs = allocs.entries;
+    smir.functions = functions.entries;
+    smir.vtables = vtables;
+    smir.calls = calls
+        .into_iter()
+        .filter(|(input, call)| item_inputs.get(&call.caller) == Some(input))
+        .map(|(_, call)| call)
+        .collect();
+    smir.uneval_consts = uneval_consts.entries;
+    smir.items = items.entries;
+    smir.generic_items = generic_items.entries;
+    smir.types = types.entries;
+    smir.spans = spans.entries;
+    smir.debug = debug;
+    Ok(Linked { smir, unresolved })
+}
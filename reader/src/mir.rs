@@ -0,0 +1,7 @@
+//! Mirrors of `stable_mir::mir`.
+
+pub mod alloc;
+mod body;
+pub mod mono;
+
+pub use body::*;
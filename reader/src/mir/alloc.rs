@@ -0,0 +1,21 @@
+//! Mirrors of `stable_mir::mir::alloc`.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::mir::mono::{Instance, StaticDef};
+use crate::ty::{Allocation, Binder, ExistentialTraitRef, Ty};
+
+/// Interned allocation handle. Keys into [`SmirJson::allocs`](crate::SmirJson::allocs).
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct AllocId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum GlobalAlloc {
+    Function(Instance),
+    VTable(Ty, Option<Binder<ExistentialTraitRef>>),
+    Static(StaticDef),
+    Memory(Allocation),
+}
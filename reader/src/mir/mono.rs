@@ -0,0 +1,30 @@
+//! Mirrors of `stable_mir::mir::mono`.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::DefId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct Instance {
+    pub kind: InstanceKind,
+    pub def: InstanceDef,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum InstanceKind {
+    Item,
+    Intrinsic,
+    Virtual { idx: usize },
+    Shim,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct InstanceDef(pub usize);
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct StaticDef(pub DefId);
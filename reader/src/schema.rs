@@ -0,0 +1,718 @@
+//! Mirrors of the `stable_mir_json::printer::schema` output types.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::abi::{FnAbi, LayoutShape, Primitive, TagEncoding, WrappingRange};
+use crate::mir::alloc::{AllocId, GlobalAlloc};
+use crate::mir::mono::InstanceKind;
+use crate::mir::{Body, Mutability, Safety};
+use crate::target::MachineInfo;
+use crate::ty::{
+    Abi, AdtDef, AliasKind, AliasTy, Allocation, Binder, ClosureDef, ConstDef, CoroutineDef,
+    ExistentialTraitRef, ForeignDef, ForeignItemKind, GenericArgs, Generics, ParamTy, Pattern,
+    PredicateKind, RigidTy, Span, Ty, TyConst, TyKind,
+};
+use crate::{DefId, ItemKind};
+
+/// Output format revision this crate reads and writes.
+pub const SCHEMA_VERSION: u32 = 14;
+
+/// Top-level structure of a `*.smir.json` file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SmirJson {
+    pub header: SmirJsonHeader,
+    pub name: String,
+    pub crate_id: u64,
+    pub allocs: Vec<AllocInfo>,
+    pub functions: Vec<(LinkMapKey, FnSymType, InstanceKindTag)>,
+    pub vtables: Vec<VTableInfo>,
+    pub calls: Vec<CallEdge>,
+    pub uneval_consts: Vec<(ConstDef, String)>,
+    pub items: Vec<Item>,
+    pub generic_items: Vec<GenericItem>,
+    pub types: Vec<(Ty, TypeMetadata)>,
+    pub spans: Vec<(Span, SourceData)>,
+    pub debug: Option<SmirJsonDebugInfo>,
+    pub machine: MachineInfo,
+}
+
+/// `index.json` of a split output directory: every field of [`SmirJson`]
+/// except `items`, which lists the item files instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SplitIndex {
+    pub header: SmirJsonHeader,
+    pub name: String,
+    pub crate_id: u64,
+    pub allocs: Vec<AllocInfo>,
+    pub functions: Vec<(LinkMapKey, FnSymType, InstanceKindTag)>,
+    pub vtables: Vec<VTableInfo>,
+    pub calls: Vec<CallEdge>,
+    pub uneval_consts: Vec<(ConstDef, String)>,
+    pub items: Vec<ItemEntry>,
+    pub generic_items: Vec<GenericItem>,
+    pub types: Vec<(Ty, TypeMetadata)>,
+    pub spans: Vec<(Span, SourceData)>,
+    pub debug: Option<SmirJsonDebugInfo>,
+    pub machine: MachineInfo,
+}
+
+impl SplitIndex {
+    /// The single-file value, given the items listed in the index (in order).
+    pub fn into_smir_json(self, items: Vec<Item>) -> SmirJson {
+        SmirJson {
+            header: self.header,
+            name: self.name,
+            crate_id: self.crate_id,
+            allocs: self.allocs,
+            functions: self.functions,
+            vtables: self.vtables,
+            calls: self.calls,
+            uneval_consts: self.uneval_consts,
+            items,
+            generic_items: self.generic_items,
+            types: self.types,
+            spans: self.spans,
+            debug: self.debug,
+            machine: self.machine,
+        }
+    }
+}
+
+/// An item file listed in a [`SplitIndex`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemEntry {
+    pub symbol_name: String,
+    /// Path of the item file, relative to the `items` directory.
+    pub file: String,
+}
+
+/// Format revision, producer, and shape-affecting flags of a file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SmirJsonHeader {
+    pub schema_version: u32,
+    pub producer: String,
+    pub producer_version: String,
+    pub rustc_version: String,
+    pub rustc_commit: String,
+    pub flags: OutputFlags,
+}
+
+/// Output flags the producer ran with, from its command line or environment.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OutputFlags {
+    pub link_items: bool,
+    pub link_inst: bool,
+    pub debug: bool,
+    pub stable_ids: bool,
+    pub filter: ItemFilter,
+    pub polymorphic: bool,
+    pub upstream: Option<UpstreamBodies>,
+}
+
+/// Item selection the producer ran with (`--smir-filter-path`,
+/// `--smir-reachable-from`); both empty unless the output was restricted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemFilter {
+    pub paths: Vec<String>,
+    pub reachable_from: Vec<String>,
+}
+
+/// Upstream bodies the producer included (`--smir-upstream`,
+/// `--smir-upstream-depth`, `--smir-upstream-crate`).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct UpstreamBodies {
+    /// Greatest depth of an added callee; unlimited if `None`.
+    pub depth: Option<usize>,
+    /// Names of the crates whose functions may be added; any if empty.
+    pub crates: Vec<String>,
+}
+
+/// Span location data: `(filename, start_line, start_col, end_line, end_col)`.
+pub type SourceData = (String, usize, usize, usize, usize);
+
+/// A global allocation referenced from a MIR body.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AllocInfo {
+    pub alloc_id: AllocId,
+    pub ty: Ty,
+    pub global_alloc: GlobalAlloc,
+    /// The contents of a memory allocation read as a value of type `ty`.
+    pub decoded: Option<DecodedValue>,
+}
+
+/// A value read from the bytes of an allocation (see [`AllocInfo::decoded`]).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum DecodedValue {
+    Bool(bool),
+    Int(i128),
+    Uint(u128),
+    /// A float in Rust's decimal notation (`-0.5`, `inf`, `NaN`).
+    Float(String),
+    Char(char),
+    Str(String),
+    /// A pointer into allocation `alloc_id` at `offset` (or, without
+    /// `alloc_id`, the address `offset`), with the metadata of a wide pointer.
+    Ptr {
+        alloc_id: Option<AllocId>,
+        offset: u64,
+        metadata: Option<Box<DecodedValue>>,
+    },
+    Aggregate(Vec<DecodedValue>),
+    Enum {
+        variant: usize,
+        fields: Vec<DecodedValue>,
+    },
+    Array(Vec<DecodedValue>),
+    /// Raw bytes; `None` marks an uninitialized byte.
+    Bytes(Vec<Option<u8>>),
+}
+
+/// The vtable of a concrete type used as a trait object. `entries` has one
+/// entry per pointer-sized slot, so the index of an
+/// [`InstanceKind::Virtual`] call selects its entry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct VTableInfo {
+    pub ty: Ty,
+    /// The principal trait; `None` for a `dyn` of auto traits only.
+    pub trait_ref: Option<Binder<ExistentialTraitRef>>,
+    pub trait_name: Option<String>,
+    pub size: u64,
+    pub align: u64,
+    pub entries: Vec<VTableEntry>,
+}
+
+/// A slot of a [`VTableInfo`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum VTableEntry {
+    /// The drop glue, absent if the type needs no drop.
+    MetadataDropInPlace(Option<VTableFn>),
+    MetadataSize,
+    MetadataAlign,
+    /// A method that cannot be called on a trait object.
+    Vacant,
+    Method(VTableFn),
+    /// A pointer to the vtable of the same type as the given supertrait.
+    TraitVPtr(Binder<ExistentialTraitRef>),
+}
+
+/// A function in a vtable slot. `key` looks it up in
+/// [`SmirJson::functions`]; closure bodies have none, nor have reify and
+/// vtable shims without `LINK_INST`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct VTableFn {
+    pub key: Option<LinkMapKey>,
+    pub symbol: FnSymType,
+}
+
+/// A call site (or function pointer) in the body of an item: `caller` is
+/// the item's symbol name, `span` the call terminator or the statement
+/// creating the pointer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CallEdge {
+    pub caller: String,
+    pub callee: FnSymType,
+    pub kind: CallKind,
+    pub span: Span,
+}
+
+/// How a [`CallEdge`] reaches its callee.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum CallKind {
+    /// A call of a statically known function.
+    Direct,
+    /// A `ReifyFnPointer` cast, taking a pointer to the callee.
+    FnPtr,
+    /// A drop, calling the drop glue of the dropped type.
+    DropGlue,
+    /// A call through a `dyn Trait`, of the method in slot `idx` of the
+    /// receiver's vtable (see [`VTableInfo::entries`]).
+    Virtual { idx: usize },
+}
+
+/// Key into the link-time function map.
+///
+/// Written as a bare type index by default, or as a `[ty, instance_kind]`
+/// pair when the producer ran with `LINK_INST` set; `instance_kind` is
+/// `Some` exactly in the latter case.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LinkMapKey {
+    pub ty: Ty,
+    pub instance_kind: Option<InstanceKindKey>,
+}
+
+/// The internal `InstanceKind` of a [`LinkMapKey`]: the variant, with the
+/// item and types it refers to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum InstanceKindKey {
+    Item(DefId),
+    Intrinsic(DefId),
+    VTableShim(DefId),
+    ReifyShim(DefId, Option<ReifyReason>),
+    FnPtrShim(DefId, Ty),
+    Virtual(DefId, usize),
+    ClosureOnceShim {
+        call_once: DefId,
+        track_caller: bool,
+    },
+    ConstructCoroutineInClosureShim {
+        coroutine_closure_def_id: DefId,
+        receiver_by_ref: bool,
+    },
+    ThreadLocalShim(DefId),
+    DropGlue(DefId, Option<Ty>),
+    CloneShim(DefId, Ty),
+    FnPtrAddrShim(DefId, Ty),
+    AsyncDropGlueCtorShim(DefId, Option<Ty>),
+}
+
+impl InstanceKindKey {
+    /// The variant, without the data.
+    pub fn tag(&self) -> InstanceKindTag {
+        match self {
+            InstanceKindKey::Item(..) => InstanceKindTag::Item,
+            InstanceKindKey::Intrinsic(..) => InstanceKindTag::Intrinsic,
+            InstanceKindKey::VTableShim(..) => InstanceKindTag::VTableShim,
+            InstanceKindKey::ReifyShim(..) => InstanceKindTag::ReifyShim,
+            InstanceKindKey::FnPtrShim(..) => InstanceKindTag::FnPtrShim,
+            InstanceKindKey::Virtual(..) => InstanceKindTag::Virtual,
+            InstanceKindKey::ClosureOnceShim { .. } => InstanceKindTag::ClosureOnceShim,
+            InstanceKindKey::ConstructCoroutineInClosureShim { .. } => {
+                InstanceKindTag::ConstructCoroutineInClosureShim
+            }
+            InstanceKindKey::ThreadLocalShim(..) => InstanceKindTag::ThreadLocalShim,
+            InstanceKindKey::DropGlue(..) => InstanceKindTag::DropGlue,
+            InstanceKindKey::CloneShim(..) => InstanceKindTag::CloneShim,
+            InstanceKindKey::FnPtrAddrShim(..) => InstanceKindTag::FnPtrAddrShim,
+            InstanceKindKey::AsyncDropGlueCtorShim(..) => InstanceKindTag::AsyncDropGlueCtorShim,
+        }
+    }
+}
+
+/// Why a `ReifyShim` was generated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ReifyReason {
+    FnPtr,
+    Vtable,
+}
+
+impl Serialize for LinkMapKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.instance_kind {
+            Some(kind) => {
+                let mut tup = serializer.serialize_tuple(2)?;
+                tup.serialize_element(&self.ty)?;
+                tup.serialize_element(kind)?;
+                tup.end()
+            }
+            None => self.ty.serialize(serializer),
+        }
+    }
+}
+
+impl JsonSchema for LinkMapKey {
+    fn schema_name() -> Cow<'static, str> {
+        "LinkMapKey".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let ty = generator.subschema_for::<Ty>();
+        let kind = generator.subschema_for::<InstanceKindKey>();
+        json_schema!({
+            "anyOf": [
+                ty,
+                {
+                    "type": "array",
+                    "prefixItems": [ty, kind],
+                    "minItems": 2,
+                    "maxItems": 2
+                }
+            ]
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for LinkMapKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = LinkMapKey;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a type index or a [type index, instance kind] pair")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<LinkMapKey, E> {
+                Ok(LinkMapKey {
+                    ty: Ty(v as usize),
+                    instance_kind: None,
+                })
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<LinkMapKey, A::Error> {
+                let ty = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let kind = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(LinkMapKey {
+                    ty,
+                    instance_kind: Some(kind),
+                })
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+/// How a function symbol resolved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum FnSymType {
+    NoOpSym(String),
+    IntrinsicSym(String),
+    NormalSym(String),
+}
+
+/// A monomorphized item (function, static, or global asm).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Item {
+    pub symbol_name: String,
+    pub mono_item_kind: MonoItemKind,
+    /// The upstream crate the body was taken from (`--smir-upstream`);
+    /// `None` for the local crate's mono items.
+    pub external_crate: Option<String>,
+    pub details: Option<ItemDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum MonoItemKind {
+    MonoItemFn {
+        name: String,
+        id: DefId,
+        instance_kind: InstanceKindTag,
+        body: Option<Body>,
+        fn_abi: Option<Box<FnAbi>>,
+    },
+    MonoItemStatic {
+        name: String,
+        id: DefId,
+        allocation: Option<Allocation>,
+    },
+    MonoItemGlobalAsm {
+        asm: String,
+    },
+}
+
+/// A generic function of the local crate with its polymorphic body, present
+/// when the producer ran with `--smir-polymorphic`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct GenericItem {
+    pub name: String,
+    pub id: DefId,
+    /// Generics of the item and its parents, outermost first.
+    pub generics: Vec<Generics>,
+    /// Predicates in scope in the body, including those of the parents.
+    pub predicates: Vec<(PredicateKind, Span)>,
+    pub body: Body,
+}
+
+/// Per-item debug details, present when the producer ran with `DEBUG` set.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemDetails {
+    pub fn_instance_kind: Option<InstanceKind>,
+    pub fn_item_kind: Option<ItemKind>,
+    pub fn_body_details: Option<BodyDetails>,
+    pub internal_kind: String,
+    pub path: String,
+    pub internal_ty: String,
+    pub generic_data: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BodyDetails {
+    pub pp: String,
+}
+
+/// Structured metadata about a type, keyed by [`Ty`] in [`SmirJson::types`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TypeMetadata {
+    PrimitiveType(RigidTy),
+    EnumType {
+        name: String,
+        adt_def: AdtDef,
+        discriminants: Vec<u128>,
+        fields: Vec<Vec<Ty>>,
+        variant_names: Vec<String>,
+        field_names: Vec<Vec<String>>,
+        generic_args: GenericArgs,
+        repr: AdtRepr,
+        layout: Option<LayoutShape>,
+        enum_layout: Option<EnumLayout>,
+    },
+    StructType {
+        name: String,
+        adt_def: AdtDef,
+        fields: Vec<Ty>,
+        field_names: Vec<String>,
+        generic_args: GenericArgs,
+        repr: AdtRepr,
+        layout: Option<LayoutShape>,
+    },
+    UnionType {
+        name: String,
+        adt_def: AdtDef,
+        fields: Vec<Ty>,
+        field_names: Vec<String>,
+        generic_args: GenericArgs,
+        repr: AdtRepr,
+        layout: Option<LayoutShape>,
+    },
+    ArrayType {
+        elem_type: Ty,
+        size: Option<TyConst>,
+        layout: Option<LayoutShape>,
+    },
+    PtrType {
+        pointee_type: Ty,
+        layout: Option<LayoutShape>,
+        mutability: Mutability,
+    },
+    RefType {
+        pointee_type: Ty,
+        layout: Option<LayoutShape>,
+        mutability: Mutability,
+    },
+    TupleType {
+        types: Vec<Ty>,
+        layout: Option<LayoutShape>,
+    },
+    DynType {
+        name: String,
+        layout: Option<LayoutShape>,
+    },
+    /// A function definition or function pointer.
+    FnSigType {
+        name: String,
+        sig: FnSignature,
+        layout: Option<LayoutShape>,
+    },
+    /// A closure; its captured variables are the fields of its layout.
+    ClosureType {
+        name: String,
+        closure_def: ClosureDef,
+        sig: FnSignature,
+        upvar_tys: Vec<Ty>,
+        layout: Option<LayoutShape>,
+    },
+    /// A coroutine; each entry of `variants` lists the `saved_locals` held
+    /// by a state variant of the layout, starting with the unresumed,
+    /// returned and poisoned states.
+    CoroutineType {
+        name: String,
+        coroutine_def: CoroutineDef,
+        upvar_tys: Vec<Ty>,
+        saved_locals: Vec<Ty>,
+        variants: Vec<Vec<usize>>,
+        layout: Option<LayoutShape>,
+    },
+    /// `base` restricted to the values matching `pattern`.
+    PatternType {
+        base: Ty,
+        pattern: Pattern,
+        layout: Option<LayoutShape>,
+    },
+    /// An extern type.
+    ForeignType {
+        name: String,
+        foreign_def: ForeignDef,
+        layout: Option<LayoutShape>,
+    },
+    VoidType,
+    /// A generic parameter; only in the bodies of `generic_items`.
+    ParamType(ParamTy),
+    /// An unnormalized projection or opaque type; only in the bodies of
+    /// `generic_items`.
+    AliasType {
+        name: String,
+        kind: AliasKind,
+        alias: AliasTy,
+    },
+}
+
+/// The signature of a function type (see [`TypeMetadata::FnSigType`]); a
+/// closure takes its arguments as one tuple.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FnSignature {
+    pub inputs: Vec<Ty>,
+    pub output: Ty,
+    pub abi: Abi,
+    pub c_variadic: bool,
+    pub safety: Safety,
+}
+
+/// The `#[repr]` options of an ADT.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AdtRepr {
+    pub c: bool,
+    pub transparent: bool,
+    pub simd: bool,
+    /// The integer type of `#[repr(u8)]` etc.: `RigidTy::Int` or `RigidTy::Uint`.
+    pub int: Option<RigidTy>,
+    /// `#[repr(packed(N))]`, in bytes.
+    pub packed: Option<u64>,
+    /// `#[repr(align(N))]`, in bytes.
+    pub align: Option<u64>,
+}
+
+/// The tag and variant layouts of an enum (see [`TypeMetadata::EnumType`]).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EnumLayout {
+    /// `None` if the enum has no tag.
+    pub tag: Option<EnumTag>,
+    /// One entry per variant, in declaration order.
+    pub variants: Vec<Option<VariantLayout>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EnumTag {
+    pub offset: usize,
+    pub size: usize,
+    pub primitive: Primitive,
+    pub valid_range: WrappingRange,
+    pub encoding: TagEncoding,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct VariantLayout {
+    pub field_offsets: Vec<usize>,
+    /// `None` for the untagged variant of a niche encoding.
+    pub tag_value: Option<u128>,
+    pub uninhabited: bool,
+}
+
+/// Extra information present when the producer ran with `DEBUG` set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SmirJsonDebugInfo {
+    pub fn_sources: Vec<(LinkMapKey, Vec<ItemSource>)>,
+    pub link_conflicts: Vec<LinkMapConflict>,
+    /// Written as a JSON object keyed by type index; kept in file order.
+    #[serde(with = "ordered_map")]
+    #[schemars(with = "BTreeMap<String, (TyKind, Option<LayoutShape>)>")]
+    pub types: Vec<(Ty, (TyKind, Option<LayoutShape>))>,
+    pub foreign_modules: Vec<(String, Vec<ForeignModule>)>,
+}
+
+/// The variant of rustc's internal `InstanceKind`: a function's own body,
+/// an intrinsic, a virtual call, or one of the compiler-generated shims.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum InstanceKindTag {
+    Item,
+    Intrinsic,
+    VTableShim,
+    ReifyShim,
+    FnPtrShim,
+    Virtual,
+    ClosureOnceShim,
+    ConstructCoroutineInClosureShim,
+    ThreadLocalShim,
+    DropGlue,
+    CloneShim,
+    FnPtrAddrShim,
+    AsyncDropGlueCtorShim,
+}
+
+/// A symbol found for a `functions` key that already had a different one
+/// (only possible with `LINK_INST`); the symbol found first is kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LinkMapConflict {
+    pub key: LinkMapKey,
+    pub kept: LinkMapCandidate,
+    pub rejected: LinkMapCandidate,
+}
+
+/// A symbol for a `functions` key and where it was found.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LinkMapCandidate {
+    pub symbol: FnSymType,
+    pub sources: Vec<ItemSource>,
+}
+
+/// Where a link map entry was discovered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ItemSource {
+    Item,
+    Term,
+    Fptr,
+    Vtbl,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ForeignModule {
+    pub name: String,
+    pub items: Vec<ForeignItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ForeignItem {
+    pub name: String,
+    pub kind: ForeignItemKind,
+}
+
+/// (De)serializes a `Vec<(K, V)>` as a map, preserving entry order.
+mod ordered_map {
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use serde::de::{MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(entries: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (k, v) in entries {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<Vec<(K, V)>, D::Error>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<K, V> {
+            type Value = Vec<(K, V)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(entries)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,314 @@
+//! Command-line options of the `stable_mir_json` driver.
+//!
+//! Options of this tool all start with `--smir-` and may appear anywhere on
+//! the command line; every other argument is passed on to rustc unchanged.
+//! Output flags not given on the command line fall back to the `LINK_ITEMS`,
+//! `LINK_INST`, `DEBUG` and `STABLE_IDS` environment variables (see
+//! [`OutputFlags::from_env`]). For backward compatibility, a first argument
+//! of `--json`, `--dot` or `--d2` selects the output format.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::compat::middle::ty::TyCtxt;
+use crate::compat::output::{output_dest, OutputDest};
+use crate::printer::{collect_smir, OutputFlags, SmirJson};
+
+pub const USAGE: &str = "\
+Usage: stable_mir_json [OPTIONS] [RUSTC ARGS]
+
+Options:
+  --smir-format=FORMAT[,FORMAT...]  Output formats: json (default), dot, d2, cbor,
+                                    split (a directory with one file per item),
+                                    calls-dot, calls-graphml (the call graph)
+  --smir-out=PATH                   Write the output to PATH (`-` for stdout,
+                                    except for split) instead of next to
+                                    rustc's MIR output; only valid with a
+                                    single format
+  --smir-filter-path=GLOB           Only keep items whose def path matches GLOB
+                                    (`*` matches anything, `?` one character);
+                                    may be repeated
+  --smir-reachable-from=PATH        Only keep items reachable from the item with
+                                    def path PATH; may be repeated
+  --smir-polymorphic                Also emit the polymorphic bodies of generic
+                                    functions of the crate
+  --smir-upstream                   Also emit the bodies of upstream functions
+                                    called by the crate, transitively
+  --smir-upstream-depth=N           Like --smir-upstream, following calls at
+                                    most N levels deep
+  --smir-upstream-crate=NAME        Like --smir-upstream, only adding functions
+                                    of crate NAME; may be repeated
+  --smir-link-items                 Add link map entries for all mono items (LINK_ITEMS)
+  --smir-link-inst                  Key the link map by type and instance kind (LINK_INST)
+  --smir-debug                      Include debug information (DEBUG)
+  --smir-stable-ids                 Use content-derived IDs (STABLE_IDS)
+  --smir-help                       Print this help
+
+All other arguments are passed to rustc.";
+
+/// An output file format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Dot,
+    D2,
+    Cbor,
+    /// A directory with an index file and one JSON file per item.
+    Split,
+    /// The call graph in DOT format.
+    CallsDot,
+    /// The call graph in GraphML format.
+    CallsGraphml,
+}
+
+impl OutputFormat {
+    const ALL: [(&'static str, OutputFormat); 7] = [
+        ("json", OutputFormat::Json),
+        ("dot", OutputFormat::Dot),
+        ("d2", OutputFormat::D2),
+        ("cbor", OutputFormat::Cbor),
+        ("split", OutputFormat::Split),
+        ("calls-dot", OutputFormat::CallsDot),
+        ("calls-graphml", OutputFormat::CallsGraphml),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, format)| *format)
+    }
+
+    /// Extension of the output file (or directory), replacing that of rustc's
+    /// MIR output.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "smir.json",
+            OutputFormat::Dot => "smir.dot",
+            OutputFormat::D2 => "smir.d2",
+            OutputFormat::Cbor => "smir.cbor",
+            OutputFormat::Split => "smir",
+            OutputFormat::CallsDot => "smir.calls.dot",
+            OutputFormat::CallsGraphml => "smir.calls.graphml",
+        }
+    }
+
+    /// Write collected output in this format to `dest`, panicking on I/O
+    /// errors.
+    pub fn emit(self, smir: &SmirJson, dest: &OutputDest) {
+        match self {
+            OutputFormat::Json => dest.write_with(|w| Ok(smir.write_json(w)?)),
+            OutputFormat::Dot => dest.write(&smir.to_dot_file()),
+            OutputFormat::D2 => dest.write(&smir.to_d2_file()),
+            OutputFormat::Cbor => dest.write_with(|w| smir.write_cbor(w)),
+            OutputFormat::Split => dest.write_dir(|dir| smir.write_split(dir)),
+            OutputFormat::CallsDot => dest.write(&smir.to_calls_dot_file()),
+            OutputFormat::CallsGraphml => dest.write(&smir.to_calls_graphml_file()),
+        }
+    }
+}
+
+/// Parsed command line: the tool's own options and the remaining rustc
+/// arguments (including the program name in first position).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub formats: Vec<OutputFormat>,
+    pub flags: OutputFlags,
+    /// Explicit output path; `-` means stdout.
+    pub out: Option<PathBuf>,
+    pub help: bool,
+    pub rustc_args: Vec<String>,
+}
+
+/// An invalid command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CliError {
+    UnknownOption(String),
+    UnknownFormat(String),
+    MissingValue(&'static str),
+    UnexpectedValue(&'static str),
+    InvalidValue(&'static str, String),
+    Repeated(&'static str),
+    OutWithSeveralFormats,
+    SplitToStdout,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(opt) => write!(f, "unknown option `{opt}`"),
+            CliError::UnknownFormat(name) => {
+                let known = OutputFormat::ALL.map(|(n, _)| n).join(", ");
+                write!(
+                    f,
+                    "unknown output format `{name}` (expected one of: {known})"
+                )
+            }
+            CliError::MissingValue(opt) => write!(f, "`{opt}` requires a value"),
+            CliError::UnexpectedValue(opt) => write!(f, "`{opt}` does not take a value"),
+            CliError::InvalidValue(opt, value) => write!(f, "invalid value `{value}` for `{opt}`"),
+            CliError::Repeated(what) => write!(f, "{what} given more than once"),
+            CliError::SplitToStdout => {
+                write!(f, "`split` output is a directory and cannot go to stdout")
+            }
+            CliError::OutWithSeveralFormats => {
+                write!(
+                    f,
+                    "`--smir-out` can only be used with a single output format"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Options {
+    /// Split `args` (as from [`std::env::args`]) into the tool's options and
+    /// rustc's arguments, with output flags defaulting to the environment.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        Self::parse_with_flags(args, OutputFlags::from_env())
+    }
+
+    /// Like [`Options::parse`], starting from the given output flags instead
+    /// of the environment.
+    pub fn parse_with_flags(
+        args: impl IntoIterator<Item = String>,
+        flags: OutputFlags,
+    ) -> Result<Self, CliError> {
+        let mut options = Options {
+            formats: Vec::new(),
+            flags,
+            out: None,
+            help: false,
+            rustc_args: Vec::new(),
+        };
+        let mut args = args.into_iter().peekable();
+        options.rustc_args.extend(args.next());
+
+        let legacy_format = match args.peek().map(String::as_str) {
+            Some("--json") => Some(OutputFormat::Json),
+            Some("--dot") => Some(OutputFormat::Dot),
+            Some("--d2") => Some(OutputFormat::D2),
+            _ => None,
+        };
+        if let Some(format) = legacy_format {
+            args.next();
+            options.formats.push(format);
+        }
+
+        while let Some(arg) = args.next() {
+            let Some(opt) = arg.strip_prefix("--smir-") else {
+                options.rustc_args.push(arg);
+                continue;
+            };
+            let (name, value) = match opt.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (opt, None),
+            };
+            match name {
+                "format" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or(CliError::MissingValue("--smir-format"))?;
+                    if !options.formats.is_empty() {
+                        return Err(CliError::Repeated("output format"));
+                    }
+                    for name in value.split(',') {
+                        let format = OutputFormat::from_name(name)
+                            .ok_or_else(|| CliError::UnknownFormat(name.to_string()))?;
+                        if !options.formats.contains(&format) {
+                            options.formats.push(format);
+                        }
+                    }
+                }
+                "filter-path" | "reachable-from" => {
+                    let (opt, list) = match name {
+                        "filter-path" => ("--smir-filter-path", &mut options.flags.filter.paths),
+                        _ => (
+                            "--smir-reachable-from",
+                            &mut options.flags.filter.reachable_from,
+                        ),
+                    };
+                    list.push(
+                        value
+                            .or_else(|| args.next())
+                            .ok_or(CliError::MissingValue(opt))?,
+                    );
+                }
+                "upstream" => {
+                    if value.is_some() {
+                        return Err(CliError::UnexpectedValue("--smir-upstream"));
+                    }
+                    options.flags.upstream.get_or_insert_default();
+                }
+                "upstream-depth" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or(CliError::MissingValue("--smir-upstream-depth"))?;
+                    let depth = value
+                        .parse()
+                        .map_err(|_| CliError::InvalidValue("--smir-upstream-depth", value))?;
+                    let upstream = options.flags.upstream.get_or_insert_default();
+                    if upstream.depth.replace(depth).is_some() {
+                        return Err(CliError::Repeated("`--smir-upstream-depth`"));
+                    }
+                }
+                "upstream-crate" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or(CliError::MissingValue("--smir-upstream-crate"))?;
+                    let upstream = options.flags.upstream.get_or_insert_default();
+                    upstream.crates.push(value);
+                }
+                "out" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or(CliError::MissingValue("--smir-out"))?;
+                    if options.out.replace(value.into()).is_some() {
+                        return Err(CliError::Repeated("`--smir-out`"));
+                    }
+                }
+                _ => {
+                    let (opt, flag) = match name {
+                        "link-items" => ("--smir-link-items", &mut options.flags.link_items),
+                        "link-inst" => ("--smir-link-inst", &mut options.flags.link_inst),
+                        "debug" => ("--smir-debug", &mut options.flags.debug),
+                        "stable-ids" => ("--smir-stable-ids", &mut options.flags.stable_ids),
+                        "polymorphic" => ("--smir-polymorphic", &mut options.flags.polymorphic),
+                        "help" => ("--smir-help", &mut options.help),
+                        _ => return Err(CliError::UnknownOption(arg)),
+                    };
+                    if value.is_some() {
+                        return Err(CliError::UnexpectedValue(opt));
+                    }
+                    *flag = true;
+                }
+            }
+        }
+
+        if options.formats.is_empty() {
+            options.formats.push(OutputFormat::Json);
+        }
+        if options.out.is_some() && options.formats.len() > 1 {
+            return Err(CliError::OutWithSeveralFormats);
+        }
+        if options.out.as_deref() == Some(Path::new("-"))
+            && options.formats.contains(&OutputFormat::Split)
+        {
+            return Err(CliError::SplitToStdout);
+        }
+        Ok(options)
+    }
+
+    /// Collect the crate once and write every selected output format.
+    pub fn emit(&self, tcx: TyCtxt<'_>) {
+        let smir = collect_smir(tcx, &self.flags);
+        for format in &self.formats {
+            format.emit(
+                &smir,
+                &output_dest(tcx, self.out.as_deref(), format.extension()),
+            );
+        }
+    }
+}
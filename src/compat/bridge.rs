@@ -1,50 +1,95 @@
-//! Stable<->internal conversions and OpaqueInstanceKind.
+//! Stable<->internal conversions and InstanceKindRepr.
 //!
 //! This module wraps rustc-internal instance kind queries behind an owned,
 //! lifetime-free representation so that the rest of the codebase doesn't
 //! need to carry `'tcx` lifetimes for link map keys.
 
-use std::hash::{Hash, Hasher};
-
 use super::middle;
 use super::rustc_internal;
+use super::serde::Serialize;
 use super::stable_mir;
 use super::TyCtxt;
 use stable_mir::mir::mono::Instance;
+use stable_mir::CrateDef;
 
-/// Owned, lifetime-free replacement for `middle::ty::InstanceKind<'tcx>`.
-///
-/// The actual `InstanceKind` usage is narrow:
-/// 1. Serialized as `format!("{:?}", kind)` (a Debug string)
-/// 2. Checked via `is_reify_shim()` (a single pattern match)
-/// 3. Used for `Hash`/`Eq` in `LinkMapKey` (map keying)
-///
-/// This struct captures all three via owned data, eliminating the need
-/// to propagate the `'tcx` lifetime through `LinkMapKey`, `FnSymInfo`,
-/// `SmirJson`, and `SmirJsonDebugInfo`.
-#[derive(Clone, Debug)]
-pub struct OpaqueInstanceKind {
-    debug_repr: String,
-    pub is_reify_shim: bool,
+/// The variant of rustc's internal `InstanceKind`: whether an instance is a
+/// function's own body (`Item`), an intrinsic, a virtual call, or one of the
+/// shims the compiler generates. Stable MIR only tells `Shim`s apart as a
+/// whole.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum InstanceKindTag {
+    Item,
+    Intrinsic,
+    VTableShim,
+    ReifyShim,
+    FnPtrShim,
+    Virtual,
+    ClosureOnceShim,
+    ConstructCoroutineInClosureShim,
+    ThreadLocalShim,
+    DropGlue,
+    CloneShim,
+    FnPtrAddrShim,
+    AsyncDropGlueCtorShim,
 }
 
-impl PartialEq for OpaqueInstanceKind {
-    fn eq(&self, other: &Self) -> bool {
-        self.debug_repr == other.debug_repr
-    }
+/// Owned, lifetime-free replacement for `middle::ty::InstanceKind<'tcx>`.
+///
+/// Mirrors rustc's variants with the item (`DefId`) and types (`Ty`) they
+/// refer to converted to their Stable MIR handles, so that it can be used
+/// for `Hash`/`Eq` in `LinkMapKey` without carrying the `'tcx` lifetime
+/// through `LinkMapKey`, `FnSymInfo`, `SmirJson`, and `SmirJsonDebugInfo`.
+/// It serializes as the variant name with those handles, which `STABLE_IDS`
+/// replaces like any other, rather than as rustc's `Debug` output.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum InstanceKindRepr {
+    Item(stable_mir::DefId),
+    Intrinsic(stable_mir::DefId),
+    VTableShim(stable_mir::DefId),
+    ReifyShim(stable_mir::DefId, Option<ReifyReason>),
+    FnPtrShim(stable_mir::DefId, stable_mir::ty::Ty),
+    Virtual(stable_mir::DefId, usize),
+    ClosureOnceShim {
+        call_once: stable_mir::DefId,
+        track_caller: bool,
+    },
+    ConstructCoroutineInClosureShim {
+        coroutine_closure_def_id: stable_mir::DefId,
+        receiver_by_ref: bool,
+    },
+    ThreadLocalShim(stable_mir::DefId),
+    DropGlue(stable_mir::DefId, Option<stable_mir::ty::Ty>),
+    CloneShim(stable_mir::DefId, stable_mir::ty::Ty),
+    FnPtrAddrShim(stable_mir::DefId, stable_mir::ty::Ty),
+    AsyncDropGlueCtorShim(stable_mir::DefId, Option<stable_mir::ty::Ty>),
 }
 
-impl Eq for OpaqueInstanceKind {}
-
-impl Hash for OpaqueInstanceKind {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.debug_repr.hash(state);
-    }
+/// Why a `ReifyShim` was generated: for a function pointer or a vtable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum ReifyReason {
+    FnPtr,
+    Vtable,
 }
 
-impl std::fmt::Display for OpaqueInstanceKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.debug_repr)
+impl InstanceKindRepr {
+    pub fn tag(&self) -> InstanceKindTag {
+        match self {
+            InstanceKindRepr::Item(..) => InstanceKindTag::Item,
+            InstanceKindRepr::Intrinsic(..) => InstanceKindTag::Intrinsic,
+            InstanceKindRepr::VTableShim(..) => InstanceKindTag::VTableShim,
+            InstanceKindRepr::ReifyShim(..) => InstanceKindTag::ReifyShim,
+            InstanceKindRepr::FnPtrShim(..) => InstanceKindTag::FnPtrShim,
+            InstanceKindRepr::Virtual(..) => InstanceKindTag::Virtual,
+            InstanceKindRepr::ClosureOnceShim { .. } => InstanceKindTag::ClosureOnceShim,
+            InstanceKindRepr::ConstructCoroutineInClosureShim { .. } => {
+                InstanceKindTag::ConstructCoroutineInClosureShim
+            }
+            InstanceKindRepr::ThreadLocalShim(..) => InstanceKindTag::ThreadLocalShim,
+            InstanceKindRepr::DropGlue(..) => InstanceKindTag::DropGlue,
+            InstanceKindRepr::CloneShim(..) => InstanceKindTag::CloneShim,
+            InstanceKindRepr::FnPtrAddrShim(..) => InstanceKindTag::FnPtrAddrShim,
+            InstanceKindRepr::AsyncDropGlueCtorShim(..) => InstanceKindTag::AsyncDropGlueCtorShim,
+        }
     }
 }
 
@@ -82,14 +127,43 @@ pub fn resolve_unevaluated_const(
     (rustc_internal::stable(internal_mono_item), item_name)
 }
 
-/// Extract an `OpaqueInstanceKind` from a stable MIR `Instance` by
-/// converting to the internal representation and capturing the debug
-/// string and reify-shim flag.
-pub fn instance_kind(tcx: TyCtxt<'_>, inst: &Instance) -> OpaqueInstanceKind {
-    let internal_inst = rustc_internal::internal(tcx, inst);
-    let kind = internal_inst.def;
-    OpaqueInstanceKind {
-        debug_repr: format!("{:?}", kind),
-        is_reify_shim: matches!(kind, middle::ty::InstanceKind::ReifyShim(..)),
+/// Extract an `InstanceKindRepr` from a stable MIR `Instance` by
+/// converting to the internal representation and its referenced types back
+/// to Stable MIR.
+pub fn instance_kind(tcx: TyCtxt<'_>, inst: &Instance) -> InstanceKindRepr {
+    use middle::ty::InstanceKind::*;
+    // every variant refers to exactly one item, the instance's own
+    let def = inst.def.def_id();
+    let stable_ty = |ty| rustc_internal::stable(ty);
+    match rustc_internal::internal(tcx, inst).def {
+        Item(_) => InstanceKindRepr::Item(def),
+        Intrinsic(_) => InstanceKindRepr::Intrinsic(def),
+        VTableShim(_) => InstanceKindRepr::VTableShim(def),
+        ReifyShim(_, reason) => InstanceKindRepr::ReifyShim(
+            def,
+            reason.map(|reason| match reason {
+                middle::ty::ReifyReason::FnPtr => ReifyReason::FnPtr,
+                middle::ty::ReifyReason::Vtable => ReifyReason::Vtable,
+            }),
+        ),
+        FnPtrShim(_, ty) => InstanceKindRepr::FnPtrShim(def, stable_ty(ty)),
+        Virtual(_, idx) => InstanceKindRepr::Virtual(def, idx),
+        ClosureOnceShim { track_caller, .. } => InstanceKindRepr::ClosureOnceShim {
+            call_once: def,
+            track_caller,
+        },
+        ConstructCoroutineInClosureShim {
+            receiver_by_ref, ..
+        } => InstanceKindRepr::ConstructCoroutineInClosureShim {
+            coroutine_closure_def_id: def,
+            receiver_by_ref,
+        },
+        ThreadLocalShim(_) => InstanceKindRepr::ThreadLocalShim(def),
+        DropGlue(_, ty) => InstanceKindRepr::DropGlue(def, ty.map(stable_ty)),
+        CloneShim(_, ty) => InstanceKindRepr::CloneShim(def, stable_ty(ty)),
+        FnPtrAddrShim(_, ty) => InstanceKindRepr::FnPtrAddrShim(def, stable_ty(ty)),
+        AsyncDropGlueCtorShim(_, ty) => {
+            InstanceKindRepr::AsyncDropGlueCtorShim(def, ty.map(stable_ty))
+        }
     }
 }
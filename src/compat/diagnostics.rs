@@ -0,0 +1,17 @@
+//! Compiler diagnostics.
+//!
+//! Wraps `tcx.dcx()` so that warnings about the output are emitted like
+//! rustc's own: they honour `-Awarnings`, follow `--error-format`, and count
+//! towards the warning total.
+
+use super::TyCtxt;
+
+/// Emit a warning with one `note` per entry of `notes`.
+pub fn warn_with_notes(tcx: TyCtxt<'_>, message: String, notes: Vec<String>) {
+    notes
+        .into_iter()
+        .fold(tcx.dcx().struct_warn(message), |diag, note| {
+            diag.with_note(note)
+        })
+        .emit();
+}
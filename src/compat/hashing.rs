@@ -0,0 +1,36 @@
+//! Stable (content-derived) hashes for types and definitions.
+//!
+//! Wraps `tcx.type_id_hash()` and `tcx.def_path_hash()`. Both are the hashes
+//! rustc itself relies on to be identical across compilation sessions (they
+//! back `TypeId` and incremental compilation respectively), which makes them
+//! suitable as cross-run, cross-crate identifiers. Other values are hashed
+//! with rustc's `StableHasher` (128-bit SipHash with fixed keys), whose
+//! algorithm, unlike that of `std`'s `DefaultHasher`, is fixed.
+
+use std::hash::Hash;
+
+use super::internal;
+use super::rustc_data_structures::stable_hasher::{Hash64, StableHasher};
+use super::stable_mir;
+use super::TyCtxt;
+
+/// Stable hash of a type, independent of the crate being compiled and of
+/// the order in which types were interned. Regions are erased first.
+pub fn type_hash(tcx: TyCtxt<'_>, ty: stable_mir::ty::Ty) -> u128 {
+    tcx.type_id_hash(internal(tcx, ty)).as_u128()
+}
+
+/// Stable hash of a definition's path (crate identity plus def path).
+pub fn def_path_hash(tcx: TyCtxt<'_>, id: stable_mir::DefId) -> u64 {
+    tcx.def_path_hash(internal(tcx, id))
+        .0
+        .to_smaller_hash()
+        .as_u64()
+}
+
+/// Hash of `value` with rustc's `StableHasher`, keeping 64 of its 128 bits.
+pub fn stable_hash<T: Hash>(value: T) -> u64 {
+    let mut hasher = StableHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish::<Hash64>().as_u64()
+}
@@ -13,6 +13,8 @@
 //! | Module | Purpose |
 //! |--------|---------|
 //! | [`bridge`] | Stable-to-internal conversions (`Instance`, `InstanceKind`, unevaluated consts) |
+//! | [`diagnostics`] | Warnings emitted through the compiler's diagnostics (`tcx.dcx()`) |
+//! | [`hashing`] | Stable content hashes for types and definitions (`type_id_hash`, `def_path_hash`) and other values (`StableHasher`) |
 //! | [`mono_collect`] | Monomorphization collection and symbol naming |
 //! | [`output`] | Output filename resolution from the compiler session |
 //! | [`spans`] | Span-to-source-location resolution |
@@ -25,6 +27,7 @@
 //! `DefId`, etc.) without requiring them to know which rustc crate the
 //! type actually lives in.
 
+pub extern crate rustc_data_structures;
 pub extern crate rustc_middle;
 pub extern crate rustc_monomorphize;
 pub extern crate rustc_session;
@@ -52,6 +55,8 @@ pub use rustc_smir::rustc_internal::internal;
 pub use rustc_span::def_id::DefId;
 
 pub mod bridge;
+pub mod diagnostics;
+pub mod hashing;
 pub mod mono_collect;
 pub mod output;
 pub mod spans;
@@ -3,7 +3,9 @@
 //! Wraps `tcx.output_filenames().path(OutputType::Mir)` so that callers
 //! don't need to import `rustc_session` directly.
 
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 use super::rustc_session::config::{OutFileName, OutputType};
 use super::TyCtxt;
@@ -14,6 +16,46 @@ pub enum OutputDest {
     File(PathBuf),
 }
 
+impl OutputDest {
+    /// Run `write` on a buffered writer for this destination and flush it,
+    /// panicking on I/O errors.
+    pub fn write_with(&self, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+        match self {
+            OutputDest::Stdout => {
+                let mut b = io::BufWriter::new(io::stdout().lock());
+                write(&mut b)
+                    .and_then(|()| b.flush())
+                    .expect("Failed to write to stdout");
+            }
+            OutputDest::File(path) => {
+                let mut b = io::BufWriter::new(
+                    File::create(path)
+                        .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
+                );
+                write(&mut b)
+                    .and_then(|()| b.flush())
+                    .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Run `write` on this destination as a directory, panicking on I/O
+    /// errors or if the destination is stdout.
+    pub fn write_dir(&self, write: impl FnOnce(&Path) -> io::Result<()>) {
+        match self {
+            OutputDest::Stdout => panic!("Directory output cannot be written to stdout"),
+            OutputDest::File(path) => {
+                write(path).unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e))
+            }
+        }
+    }
+
+    /// Write `contents` to this destination, panicking on I/O errors.
+    pub fn write(&self, contents: &str) {
+        self.write_with(|w| w.write_all(contents.as_bytes()));
+    }
+}
+
 /// Resolve the MIR output path from the compiler session, replacing
 /// the extension with the given one.
 pub fn mir_output_path(tcx: TyCtxt<'_>, extension: &str) -> OutputDest {
@@ -22,3 +64,13 @@ pub fn mir_output_path(tcx: TyCtxt<'_>, extension: &str) -> OutputDest {
         OutFileName::Real(path) => OutputDest::File(path.with_extension(extension)),
     }
 }
+
+/// Resolve an explicit output path (`-` for stdout), falling back to
+/// [`mir_output_path`] with the given extension when there is none.
+pub fn output_dest(tcx: TyCtxt<'_>, out: Option<&Path>, extension: &str) -> OutputDest {
+    match out {
+        Some(path) if path == Path::new("-") => OutputDest::Stdout,
+        Some(path) => OutputDest::File(path.to_path_buf()),
+        None => mir_output_path(tcx, extension),
+    }
+}
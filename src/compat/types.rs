@@ -2,10 +2,14 @@
 //!
 //! Wraps `tcx.generics_of()`, `tcx.predicates_of()`, `tcx.fn_sig()`,
 //! `tcx.optimized_mir()`, `tcx.def_kind()`, `tcx.type_of()`,
-//! `tcx.has_attr()`, `adt.discriminants(tcx)`, and `tcx.fn_abi_of_fn_ptr()`.
+//! `tcx.has_attr()`, `adt.discriminants(tcx)`, `adt.repr()`,
+//! `tcx.fn_abi_of_fn_ptr()`, `tcx.coroutine_layout()`, and `tcx.vtable_entries()`.
 
 use super::middle;
-use super::middle::ty::{EarlyBinder, FnSig, GenericArgs, List, Ty, TypeFoldable, TypingEnv};
+use super::middle::ty::util::IntTypeExt;
+use super::middle::ty::{
+    EarlyBinder, FnSig, GenericArgs, List, Ty, TypeFoldable, TypeVisitableExt, TypingEnv,
+};
 use super::rustc_internal::{self, internal};
 use super::rustc_span;
 use super::stable_mir;
@@ -29,6 +33,133 @@ pub fn generic_data(tcx: TyCtxt<'_>, id: DefId) -> Vec<(String, String)> {
     v
 }
 
+/// The generics of an item and its parents (outermost first) and the
+/// predicates in scope in the item, including those of its parents, in
+/// `stable_mir` form.
+///
+/// `stable_mir` only exposes these for trait declarations.
+pub fn generics_and_predicates(
+    tcx: TyCtxt<'_>,
+    id: stable_mir::DefId,
+) -> (
+    Vec<stable_mir::ty::Generics>,
+    Vec<(stable_mir::ty::PredicateKind, stable_mir::ty::Span)>,
+) {
+    let id = rustc_internal::internal(tcx, id);
+    let mut generics = Vec::new();
+    let mut next_id = Some(id);
+    while let Some(curr_id) = next_id {
+        let params = tcx.generics_of(curr_id);
+        generics.push(rustc_internal::stable(params));
+        next_id = params.parent;
+    }
+    generics.reverse();
+    let predicates = tcx.predicates_of(id).instantiate_identity(tcx);
+    let predicates = predicates
+        .predicates
+        .into_iter()
+        .zip(predicates.spans)
+        .map(|(clause, span)| {
+            (
+                rustc_internal::stable(clause.as_predicate().kind().skip_binder()),
+                rustc_internal::stable(span),
+            )
+        })
+        .collect();
+    (generics, predicates)
+}
+
+/// The type of an ADT field instantiated with `args`, like
+/// `FieldDef::ty_with_args`, but keeping projections that cannot be
+/// normalized (because `args` mention generic parameters) instead of
+/// aborting with an ICE.
+pub fn field_ty_with_args(
+    tcx: TyCtxt<'_>,
+    field: &stable_mir::ty::FieldDef,
+    args: &stable_mir::ty::GenericArgs,
+) -> stable_mir::ty::Ty {
+    let def_ty = tcx.type_of(internal(tcx, field.def));
+    let args = internal(tcx, args);
+    let ty = tcx
+        .try_instantiate_and_normalize_erasing_regions(
+            args,
+            TypingEnv::fully_monomorphized(),
+            def_ty,
+        )
+        .unwrap_or_else(|_| tcx.erase_regions(def_ty.instantiate(tcx, args)));
+    rustc_internal::stable(ty)
+}
+
+/// The captured variable types of a closure or coroutine, in field order;
+/// empty for other types.
+pub fn upvar_tys(tcx: TyCtxt<'_>, ty: stable_mir::ty::Ty) -> Vec<stable_mir::ty::Ty> {
+    let upvars = match internal(tcx, ty).kind() {
+        middle::ty::Closure(_, args) => args.as_closure().upvar_tys(),
+        middle::ty::Coroutine(_, args) => args.as_coroutine().upvar_tys(),
+        _ => List::empty(),
+    };
+    upvars.iter().map(rustc_internal::stable).collect()
+}
+
+/// The types of the locals a coroutine saves across suspension points and,
+/// for each of its state variants, the indices of the saved locals it holds.
+/// Empty if the coroutine's MIR is not available.
+pub fn coroutine_saved_locals(
+    tcx: TyCtxt<'_>,
+    ty: stable_mir::ty::Ty,
+) -> (Vec<stable_mir::ty::Ty>, Vec<Vec<usize>>) {
+    let middle::ty::Coroutine(def_id, args) = *internal(tcx, ty).kind() else {
+        return Default::default();
+    };
+    if !tcx.is_mir_available(def_id) {
+        return Default::default();
+    }
+    let Some(layout) = tcx.coroutine_layout(def_id, args.as_coroutine().kind_ty()) else {
+        return Default::default();
+    };
+    let saved_locals = layout
+        .field_tys
+        .iter()
+        .map(|saved| {
+            let ty = EarlyBinder::bind(saved.ty);
+            let ty = tcx
+                .try_instantiate_and_normalize_erasing_regions(
+                    args,
+                    TypingEnv::fully_monomorphized(),
+                    ty,
+                )
+                .unwrap_or_else(|_| tcx.erase_regions(ty.instantiate(tcx, args)));
+            rustc_internal::stable(ty)
+        })
+        .collect();
+    let variants = layout
+        .variant_fields
+        .iter()
+        .map(|fields| fields.iter().map(|local| local.as_usize()).collect())
+        .collect();
+    (saved_locals, variants)
+}
+
+/// The const-eval MIR of a function instance without generic arguments,
+/// for upstream `const fn`s whose crate encoded no optimized MIR.
+pub fn ctfe_body(
+    tcx: TyCtxt<'_>,
+    inst: &stable_mir::mir::mono::Instance,
+) -> Option<stable_mir::mir::Body> {
+    let inst = internal(tcx, inst);
+    let id = inst.def_id();
+    if !inst.args.is_empty() || !tcx.is_ctfe_mir_available(id) {
+        return None;
+    }
+    Some(rustc_internal::stable(tcx.mir_for_ctfe(id)))
+}
+
+/// Whether a type still mentions generic parameters, i.e. comes from a
+/// polymorphic body.
+pub fn has_param(tcx: TyCtxt<'_>, ty: stable_mir::ty::Ty) -> bool {
+    internal(tcx, ty).has_param()
+}
+
 /// Unwrap an `EarlyBinder` in a default manner; panic on error.
 pub fn default_unwrap_early_binder<'tcx, T>(
     tcx: TyCtxt<'tcx>,
@@ -39,10 +170,9 @@ where
     T: TypeFoldable<TyCtxt<'tcx>>,
 {
     let v_copy = v.clone();
-    let body = tcx.optimized_mir(id);
     match tcx.try_instantiate_and_normalize_erasing_regions(
         GenericArgs::identity_for_item(tcx, id),
-        body.typing_env(tcx),
+        middle::ty::TypingEnv::post_analysis(tcx, id),
         v,
     ) {
         Ok(res) => res,
@@ -58,10 +188,10 @@ pub fn print_type<'tcx>(tcx: TyCtxt<'tcx>, id: DefId, ty: EarlyBinder<'tcx, Ty<'
     let kind: &middle::ty::TyKind = ty.skip_binder().kind();
     if let middle::ty::TyKind::FnDef(fun_id, args) = kind {
         let sig0 = tcx.fn_sig(fun_id);
-        let body = tcx.optimized_mir(id);
+        // `id` need not have a body (e.g. a trait method without a default)
         let sig1 = match tcx.try_instantiate_and_normalize_erasing_regions(
             args,
-            body.typing_env(tcx),
+            middle::ty::TypingEnv::post_analysis(tcx, id),
             sig0,
         ) {
             Ok(res) => res,
@@ -105,6 +235,56 @@ pub fn adt_discriminants(tcx: TyCtxt<'_>, adt_def: stable_mir::ty::AdtDef) -> Ve
         .collect()
 }
 
+/// The `#[repr]` options of an ADT that affect its layout.
+#[derive(Clone, Debug)]
+pub struct ReprOptions {
+    pub c: bool,
+    pub transparent: bool,
+    pub simd: bool,
+    /// The integer type of `#[repr(u8)]` etc., as an `Int` or `Uint` `RigidTy`.
+    pub int: Option<stable_mir::ty::RigidTy>,
+    /// `#[repr(packed(N))]`, in bytes.
+    pub packed: Option<u64>,
+    /// `#[repr(align(N))]`, in bytes.
+    pub align: Option<u64>,
+}
+
+/// Read the `#[repr]` options of an ADT.
+pub fn adt_repr(tcx: TyCtxt<'_>, adt_def: stable_mir::ty::AdtDef) -> ReprOptions {
+    let repr = rustc_internal::internal(tcx, adt_def).repr();
+    ReprOptions {
+        c: repr.c(),
+        transparent: repr.transparent(),
+        simd: repr.simd(),
+        int: repr.int.and_then(|int| {
+            rustc_internal::stable(int.to_ty(tcx))
+                .kind()
+                .rigid()
+                .cloned()
+        }),
+        packed: repr.pack.map(|pack| pack.bytes()),
+        align: repr.align.map(|align| align.bytes()),
+    }
+}
+
+/// The signature of a function definition, function pointer or closure
+/// type, with late-bound regions erased and projections normalized; `None`
+/// for other types. A closure's signature takes its arguments as one tuple
+/// (the `"rust-call"` ABI).
+pub fn fn_sig(tcx: TyCtxt<'_>, ty: stable_mir::ty::Ty) -> Option<stable_mir::ty::FnSig> {
+    let ty = internal(tcx, ty);
+    let sig = match ty.kind() {
+        middle::ty::FnDef(..) | middle::ty::FnPtr(..) => ty.fn_sig(tcx),
+        middle::ty::Closure(_, args) => args.as_closure().sig(),
+        _ => return None,
+    };
+    let sig = tcx.instantiate_bound_regions_with_erased(sig);
+    let sig = tcx
+        .try_normalize_erasing_regions(TypingEnv::fully_monomorphized(), sig)
+        .unwrap_or(sig);
+    Some(rustc_internal::stable(sig))
+}
+
 /// Resolve the ABI of a function pointer type (via `tcx.fn_abi_of_fn_ptr`).
 pub fn fn_ptr_abi(
     tcx: TyCtxt<'_>,
@@ -119,6 +299,60 @@ pub fn fn_ptr_abi(
     )
 }
 
+/// A slot of a vtable, as laid out by rustc (`VtblEntry`).
+#[derive(Clone, Debug)]
+pub enum VTableSlot {
+    /// The drop glue of the type, or `None` if it needs no drop.
+    DropInPlace(Option<stable_mir::mir::mono::Instance>),
+    Size,
+    Align,
+    /// A method that cannot be called on a trait object.
+    Vacant,
+    Method(stable_mir::mir::mono::Instance),
+    /// A pointer to the vtable of a supertrait, for upcasting.
+    TraitVPtr(stable_mir::ty::Binder<stable_mir::ty::ExistentialTraitRef>),
+}
+
+/// The slots of the vtable for `ty` as a `dyn` of `trait_ref` (via
+/// `tcx.vtable_entries()`), in the order of the vtable's pointer-sized
+/// entries. Without a principal trait only the size, align and drop slots
+/// exist.
+pub fn vtable_slots(
+    tcx: TyCtxt<'_>,
+    ty: stable_mir::ty::Ty,
+    trait_ref: Option<&stable_mir::ty::Binder<stable_mir::ty::ExistentialTraitRef>>,
+) -> Vec<VTableSlot> {
+    use middle::ty::vtable::VtblEntry;
+    let ty = internal(tcx, ty);
+    let entries = match trait_ref {
+        Some(trait_ref) => {
+            let trait_ref = internal(tcx, trait_ref).with_self_ty(tcx, ty);
+            tcx.vtable_entries(tcx.erase_regions(trait_ref))
+        }
+        None => TyCtxt::COMMON_VTABLE_ENTRIES,
+    };
+    entries
+        .iter()
+        .map(|entry| match entry {
+            VtblEntry::MetadataDropInPlace => {
+                VTableSlot::DropInPlace(ty.needs_drop(tcx, TypingEnv::fully_monomorphized()).then(
+                    || rustc_internal::stable(middle::ty::Instance::resolve_drop_in_place(tcx, ty)),
+                ))
+            }
+            VtblEntry::MetadataSize => VTableSlot::Size,
+            VtblEntry::MetadataAlign => VTableSlot::Align,
+            VtblEntry::Vacant => VTableSlot::Vacant,
+            VtblEntry::Method(instance) => VTableSlot::Method(rustc_internal::stable(*instance)),
+            VtblEntry::TraitVPtr(trait_ref) => {
+                let trait_ref = trait_ref.map_bound(|trait_ref| {
+                    middle::ty::ExistentialTraitRef::erase_self_ty(tcx, trait_ref)
+                });
+                VTableSlot::TraitVPtr(rustc_internal::stable(trait_ref))
+            }
+        })
+        .collect()
+}
+
 /// Convert a stable DefId to an internal DefId.
 pub fn internal_def_id(tcx: TyCtxt<'_>, id: stable_mir::DefId) -> DefId {
     rustc_internal::internal(tcx, id)
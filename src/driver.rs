@@ -6,43 +6,112 @@
 //! It exports a single function:
 //!
 //! ```rust,ignore
-//! stable_mir_driver(args: &Vec<String>, callback_fn: fn (TyCtxt) -> () )
+//! stable_mir_driver(args: &[String], callback: impl FnOnce(TyCtxt) -> T + Send)
+//!     -> Result<T, DriverError>
 //! ```
 //!
 //! Calling this function is essentially equivalent to the following macro call:
 //!
 //! ```rust,ignore
-//! rustc_internal::run_with_tcx!( args, callback_fn );
+//! rustc_internal::run!( args, callback );
 //! ```
 //!
 //! However, we prefer a non-macro version for clarity and build simplicity.
+//!
+//! The callback runs after analysis, inside the `stable_mir` context, and its
+//! result is handed back to the caller. This lets a program that links this
+//! crate collect output in-process:
+//!
+//! ```rust,ignore
+//! let smir = stable_mir_driver(&args, |tcx| collect_smir(tcx, &OutputFlags::default()))?;
+//! ```
+//!
+//! Values returned this way can be serialized and inspected, but `stable_mir`
+//! methods that query the compiler (e.g. `Ty::kind`) only work inside the
+//! callback.
 
 extern crate rustc_driver;
 extern crate rustc_interface;
 extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_smir;
+use std::fmt;
+use std::sync::Once;
+
 use rustc_driver::Compilation;
 use rustc_interface::interface::Compiler;
 use rustc_middle::ty::TyCtxt;
 use rustc_smir::rustc_internal;
 
-struct StableMirCallbacks {
-    callback_fn: fn(TyCtxt) -> (),
+use crate::compat::stable_mir;
+
+/// Why [`stable_mir_driver`] did not produce a result.
+#[derive(Debug)]
+pub enum DriverError {
+    /// The compiler reported errors (already emitted as diagnostics).
+    Failed,
+    /// The compiler finished without reaching analysis, e.g. for `-vV` or
+    /// `--print` invocations, so the callback never ran.
+    Skipped,
+    /// The `stable_mir` context could not be set up around the callback.
+    StableMir(stable_mir::Error),
 }
 
-impl rustc_driver::Callbacks for StableMirCallbacks {
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriverError::Failed => write!(f, "compilation failed"),
+            DriverError::Skipped => write!(f, "compilation stopped before analysis"),
+            DriverError::StableMir(e) => write!(f, "stable MIR error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DriverError {}
+
+struct StableMirCallbacks<F, T> {
+    callback: Option<F>,
+    result: Option<Result<T, stable_mir::Error>>,
+}
+
+impl<F, T> rustc_driver::Callbacks for StableMirCallbacks<F, T>
+where
+    F: FnOnce(TyCtxt) -> T + Send,
+    T: Send,
+{
     fn after_analysis(&mut self, _compiler: &Compiler, tcx: TyCtxt) -> Compilation {
-        let _ = rustc_internal::run(tcx, || (self.callback_fn)(tcx));
+        if let Some(callback) = self.callback.take() {
+            self.result = Some(rustc_internal::run(tcx, || callback(tcx)));
+        }
 
         Compilation::Continue
     }
 }
 
-pub fn stable_mir_driver(args_outer: &[String], callback_fn: fn(TyCtxt) -> ()) {
-    let mut callbacks = StableMirCallbacks { callback_fn };
-    let early_dcx =
-        rustc_session::EarlyDiagCtxt::new(rustc_session::config::ErrorOutputType::default());
-    rustc_driver::init_rustc_env_logger(&early_dcx);
-    let _ = rustc_driver::RunCompiler::new(args_outer, &mut callbacks).run();
+pub fn stable_mir_driver<F, T>(args_outer: &[String], callback: F) -> Result<T, DriverError>
+where
+    F: FnOnce(TyCtxt) -> T + Send,
+    T: Send,
+{
+    // Installing the logger twice is fatal; embedders may run several compilations.
+    static LOGGER: Once = Once::new();
+    LOGGER.call_once(|| {
+        let early_dcx =
+            rustc_session::EarlyDiagCtxt::new(rustc_session::config::ErrorOutputType::default());
+        rustc_driver::init_rustc_env_logger(&early_dcx);
+    });
+
+    let mut callbacks = StableMirCallbacks {
+        callback: Some(callback),
+        result: None,
+    };
+    let compiled = rustc_driver::catch_fatal_errors(|| {
+        rustc_driver::RunCompiler::new(args_outer, &mut callbacks).run()
+    });
+    match (compiled, callbacks.result) {
+        (Ok(Ok(())), Some(Ok(value))) => Ok(value),
+        (Ok(Ok(())), Some(Err(e))) => Err(DriverError::StableMir(e)),
+        (Ok(Ok(())), None) => Err(DriverError::Skipped),
+        (Ok(Err(_)), _) | (Err(_), _) => Err(DriverError::Failed),
+    }
 }
@@ -1,8 +1,9 @@
 #![feature(rustc_private)]
+pub mod cli;
 pub mod compat;
 pub mod driver;
 pub mod mk_graph;
 pub mod printer;
 pub use compat::types::has_attr;
-pub use driver::stable_mir_driver;
+pub use driver::{stable_mir_driver, DriverError};
 pub use printer::*;
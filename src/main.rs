@@ -1,26 +1,29 @@
 #![feature(rustc_private)]
-use stable_mir_json::driver::stable_mir_driver;
-use stable_mir_json::mk_graph::{emit_d2file, emit_dotfile};
-use stable_mir_json::printer::emit_smir;
+use stable_mir_json::cli::{Options, USAGE};
+use stable_mir_json::driver::{stable_mir_driver, DriverError};
 use std::env;
+use std::process::ExitCode;
 
-fn main() {
-    let mut args: Vec<String> = env::args().collect();
-
-    match args.get(1) {
-        None => stable_mir_driver(&args, emit_smir), // backward compatibility
-        Some(arg) if arg == "--json" => {
-            args.remove(1);
-            stable_mir_driver(&args, emit_smir)
-        }
-        Some(arg) if arg == "--dot" => {
-            args.remove(1);
-            stable_mir_driver(&args, emit_dotfile)
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
         }
-        Some(arg) if arg == "--d2" => {
-            args.remove(1);
-            stable_mir_driver(&args, emit_d2file)
+    };
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match stable_mir_driver(&options.rustc_args, |tcx| options.emit(tcx)) {
+        // Skipped: rustc was only asked for information (e.g. `-vV` from cargo)
+        Ok(()) | Err(DriverError::Skipped) => ExitCode::SUCCESS,
+        // rustc has already reported its errors
+        Err(DriverError::Failed) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
         }
-        Some(_other) => stable_mir_driver(&args, emit_smir), // backward compatibility
     }
 }
@@ -32,7 +32,7 @@ impl GraphContext {
         let functions: HashMap<Ty, String> = smir
             .functions
             .iter()
-            .map(|(k, v)| (k.0, function_string(v.clone())))
+            .map(|(k, v, _)| (k.0, function_string(v.clone())))
             .collect();
 
         Self {
@@ -310,7 +310,7 @@ impl GraphContext {
 
         // Field details for composite types
         match &entry.kind {
-            TypeKind::Struct { fields } => {
+            TypeKind::Struct { fields } | TypeKind::Closure { upvars: fields } => {
                 for (i, field) in fields.iter().enumerate() {
                     let field_ty_name = self.types.get_name(field.ty);
                     let field_layout = self.types.get_layout(field.ty);
@@ -364,6 +364,16 @@ impl GraphContext {
                 let len_str = len.map(|l| l.to_string()).unwrap_or("?".to_string());
                 lines.push(format!("  [{}; {}]", elem_name, len_str));
             }
+            TypeKind::Coroutine { upvars, states } => {
+                for (i, &upvar) in upvars.iter().enumerate() {
+                    lines.push(format!("  upvar{}: {}", i, self.types.get_name(upvar)));
+                }
+                for (i, locals) in states.iter().enumerate() {
+                    let names: Vec<String> =
+                        locals.iter().map(|&ty| self.types.get_name(ty)).collect();
+                    lines.push(format!("  state {}: ({})", i, names.join(", ")));
+                }
+            }
             _ => {}
         }
 
@@ -77,7 +77,22 @@ pub enum TypeKind {
     },
     Dyn,
     Function,
+    /// Captured variables, laid out like struct fields.
+    Closure {
+        upvars: Vec<FieldInfo>,
+    },
+    /// Captured variables and, per state variant, the saved locals it holds.
+    Coroutine {
+        upvars: Vec<Ty>,
+        states: Vec<Vec<Ty>>,
+    },
+    Pattern {
+        base: Ty,
+    },
+    Foreign,
     Void,
+    Param,
+    Alias,
 }
 
 /// Field information for structs/unions
@@ -312,21 +327,31 @@ impl TypeEntry {
                 fields,
                 discriminants,
                 layout,
+                enum_layout,
                 ..
             } => {
                 let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
                 let variants = discriminants
                     .iter()
                     .zip(fields.iter())
-                    .map(|(&discr, variant_fields)| VariantInfo {
-                        discriminant: discr,
-                        fields: variant_fields
-                            .iter()
-                            .map(|&t| FieldInfo {
-                                ty: t,
-                                offset: None, // Enum variant offsets require variant-specific layout
-                            })
-                            .collect(),
+                    .enumerate()
+                    .map(|(i, (&discr, variant_fields))| {
+                        let variant_layout = enum_layout
+                            .as_ref()
+                            .and_then(|l| l.variants.get(i))
+                            .and_then(Option::as_ref);
+                        VariantInfo {
+                            discriminant: discr,
+                            fields: variant_fields
+                                .iter()
+                                .enumerate()
+                                .map(|(j, &t)| FieldInfo {
+                                    ty: t,
+                                    offset: variant_layout
+                                        .and_then(|v| v.field_offsets.get(j).copied()),
+                                })
+                                .collect(),
+                        }
                     })
                     .collect();
                 (name.clone(), TypeKind::Enum { variants }, layout_info)
@@ -414,8 +439,58 @@ impl TypeEntry {
                 let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
                 (name.clone(), TypeKind::Dyn, layout_info)
             }
-            TypeMetadata::FunType(name) => (name.clone(), TypeKind::Function, None),
+            TypeMetadata::FnSigType { name, layout, .. } => (
+                name.clone(),
+                TypeKind::Function,
+                layout.as_ref().map(LayoutInfo::from_shape),
+            ),
+            TypeMetadata::ClosureType {
+                name,
+                upvar_tys,
+                layout,
+                ..
+            } => {
+                let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
+                let upvars = Self::make_field_infos(upvar_tys, layout_info.as_ref());
+                (name.clone(), TypeKind::Closure { upvars }, layout_info)
+            }
+            TypeMetadata::CoroutineType {
+                name,
+                upvar_tys,
+                saved_locals,
+                variants,
+                layout,
+                ..
+            } => {
+                let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
+                let states = variants
+                    .iter()
+                    .map(|locals| locals.iter().map(|&i| saved_locals[i]).collect())
+                    .collect();
+                (
+                    name.clone(),
+                    TypeKind::Coroutine {
+                        upvars: upvar_tys.clone(),
+                        states,
+                    },
+                    layout_info,
+                )
+            }
+            TypeMetadata::PatternType { base, layout, .. } => {
+                let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
+                (
+                    format!("{}", ty),
+                    TypeKind::Pattern { base: *base },
+                    layout_info,
+                )
+            }
+            TypeMetadata::ForeignType { name, layout, .. } => {
+                let layout_info = layout.as_ref().map(LayoutInfo::from_shape);
+                (name.clone(), TypeKind::Foreign, layout_info)
+            }
             TypeMetadata::VoidType => ("()".to_string(), TypeKind::Void, None),
+            TypeMetadata::ParamType(param) => (param.name.clone(), TypeKind::Param, None),
+            TypeMetadata::AliasType { name, .. } => (name.clone(), TypeKind::Alias, None),
         };
 
         Self { name, kind, layout }
@@ -439,7 +514,9 @@ impl TypeEntry {
             desc.push_str(&format!(" ({} bytes, align {})", layout.size, layout.align));
         }
         match &self.kind {
-            TypeKind::Struct { fields } | TypeKind::Union { fields } => {
+            TypeKind::Struct { fields }
+            | TypeKind::Union { fields }
+            | TypeKind::Closure { upvars: fields } => {
                 if !fields.is_empty() {
                     desc.push_str(" { ");
                     let field_strs: Vec<String> = fields
@@ -1,14 +1,14 @@
 //! MIR graph generation module.
 //!
 //! This module provides functionality to generate graph visualizations
-//! of Rust's MIR in various formats (DOT, D2).
+//! of Rust's MIR in various formats (DOT, D2), and of the crate's call graph
+//! (DOT, GraphML).
 
-use std::fs::File;
-use std::io::{self, Write};
+use std::path::Path;
 
 use crate::compat::middle::ty::TyCtxt;
-use crate::compat::output::{mir_output_path, OutputDest};
-use crate::printer::collect_smir;
+use crate::compat::output::output_dest;
+use crate::printer::{collect_smir, OutputFlags};
 
 // Sub-modules
 pub mod context;
@@ -25,38 +25,14 @@ pub use util::GraphLabelString;
 // Entry Points
 // =============================================================================
 
-/// Entry point to write the DOT file
-pub fn emit_dotfile(tcx: TyCtxt<'_>) {
-    let smir_dot = collect_smir(tcx).to_dot_file();
-
-    match mir_output_path(tcx, "smir.dot") {
-        OutputDest::Stdout => {
-            write!(io::stdout(), "{}", smir_dot).expect("Failed to write smir.dot");
-        }
-        OutputDest::File(path) => {
-            let mut b = io::BufWriter::new(
-                File::create(&path)
-                    .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
-            );
-            write!(b, "{}", smir_dot).expect("Failed to write smir.dot");
-        }
-    }
+/// Entry point to write the DOT file (to `out` if given, `-` for stdout)
+pub fn emit_dotfile(tcx: TyCtxt<'_>, flags: &OutputFlags, out: Option<&Path>) {
+    let smir_dot = collect_smir(tcx, flags).to_dot_file();
+    output_dest(tcx, out, "smir.dot").write(&smir_dot);
 }
 
-/// Entry point to write the D2 file
-pub fn emit_d2file(tcx: TyCtxt<'_>) {
-    let smir_d2 = collect_smir(tcx).to_d2_file();
-
-    match mir_output_path(tcx, "smir.d2") {
-        OutputDest::Stdout => {
-            write!(io::stdout(), "{}", smir_d2).expect("Failed to write smir.d2");
-        }
-        OutputDest::File(path) => {
-            let mut b = io::BufWriter::new(
-                File::create(&path)
-                    .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
-            );
-            write!(b, "{}", smir_d2).expect("Failed to write smir.d2");
-        }
-    }
+/// Entry point to write the D2 file (to `out` if given, `-` for stdout)
+pub fn emit_d2file(tcx: TyCtxt<'_>, flags: &OutputFlags, out: Option<&Path>) {
+    let smir_d2 = collect_smir(tcx, flags).to_d2_file();
+    output_dest(tcx, out, "smir.d2").write(&smir_d2);
 }
@@ -0,0 +1,162 @@
+//! Call graph output in DOT (Graphviz) and GraphML formats.
+//!
+//! Renders the `calls` edges of the output, one node per function and one
+//! edge per caller, callee and kind of call (however many call sites it
+//! stands for). Functions that are not items of the output are drawn in red
+//! (DOT) or marked `defined = false` (GraphML).
+
+use std::collections::{BTreeMap, HashMap};
+
+use dot_writer::{Attributes, Color, DotWriter, Shape, Style};
+
+use crate::printer::{CallKind, SmirJson};
+use crate::MonoItemKind;
+
+use crate::mk_graph::util::{escape_xml, function_string, name_lines, short_name};
+
+/// Distinct `(caller, callee, kind)` edges with the number of call sites each
+/// stands for.
+type CallGraphEdges<'a> = BTreeMap<(&'a str, String, &'a CallKind), usize>;
+
+impl SmirJson {
+    fn call_graph_edges(&self) -> CallGraphEdges<'_> {
+        let mut edges = BTreeMap::new();
+        for call in &self.calls {
+            let key = (
+                call.caller.as_str(),
+                function_string(call.callee.clone()),
+                &call.kind,
+            );
+            *edges.entry(key).or_insert(0) += 1;
+        }
+        edges
+    }
+
+    /// Display names of the items, by symbol name.
+    fn item_names(&self) -> HashMap<&str, &str> {
+        self.items
+            .iter()
+            .filter_map(|item| match &item.mono_item_kind {
+                MonoItemKind::MonoItemFn { name, .. }
+                | MonoItemKind::MonoItemStatic { name, .. } => {
+                    Some((item.symbol_name.as_str(), name.as_str()))
+                }
+                MonoItemKind::MonoItemGlobalAsm { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Convert the call graph to DOT (Graphviz) format
+    pub fn to_calls_dot_file(&self) -> String {
+        let edges = self.call_graph_edges();
+        let names = self.item_names();
+        let mut bytes = Vec::new();
+        {
+            let mut writer = DotWriter::from(&mut bytes);
+            writer.set_pretty_print(true);
+
+            let mut graph = writer.digraph();
+            graph.set_label(&self.name[..]);
+            graph.node_attributes().set_shape(Shape::Rectangle);
+
+            for (symbol, name) in call_graph_nodes(&edges, &names) {
+                let mut node = graph.node_named(short_name(symbol));
+                node.set_label(&name_lines(name.unwrap_or(symbol)));
+                if name.is_none() {
+                    node.set_color(Color::Red);
+                }
+            }
+
+            for ((caller, callee, kind), sites) in &edges {
+                let mut attributes = graph
+                    .edge(short_name(caller), short_name(callee))
+                    .attributes();
+                let label = match sites {
+                    1 => kind_label(kind),
+                    n => format!("{} (x{n})", kind_label(kind)),
+                };
+                attributes.set_label(&label);
+                match kind {
+                    CallKind::Direct => {}
+                    CallKind::FnPtr => {
+                        attributes.set_style(Style::Dashed);
+                    }
+                    CallKind::DropGlue => {
+                        attributes.set_style(Style::Dotted);
+                    }
+                    CallKind::Virtual { .. } => {
+                        attributes.set_style(Style::Bold);
+                    }
+                }
+            }
+        }
+        String::from_utf8(bytes).expect("Error converting dot file")
+    }
+
+    /// Convert the call graph to GraphML format
+    pub fn to_calls_graphml_file(&self) -> String {
+        let edges = self.call_graph_edges();
+        let names = self.item_names();
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, domain, ty) in [
+            ("name", "node", "string"),
+            ("defined", "node", "boolean"),
+            ("kind", "edge", "string"),
+            ("sites", "edge", "int"),
+        ] {
+            out.push_str(&format!(
+                "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{id}\" attr.type=\"{ty}\"/>\n"
+            ));
+        }
+        out.push_str(&format!(
+            "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+            escape_xml(&self.name)
+        ));
+
+        for (symbol, name) in call_graph_nodes(&edges, &names) {
+            out.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"name\">{}</data>\n      <data key=\"defined\">{}</data>\n    </node>\n",
+                escape_xml(symbol),
+                escape_xml(name.unwrap_or(symbol)),
+                name.is_some()
+            ));
+        }
+        for ((caller, callee, kind), sites) in &edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"kind\">{}</data>\n      <data key=\"sites\">{sites}</data>\n    </edge>\n",
+                escape_xml(caller),
+                escape_xml(callee),
+                kind_label(kind)
+            ));
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+/// The callers and callees of `edges` by symbol name, with their display
+/// names if they are items.
+fn call_graph_nodes<'a>(
+    edges: &'a CallGraphEdges<'_>,
+    names: &HashMap<&str, &'a str>,
+) -> BTreeMap<&'a str, Option<&'a str>> {
+    let mut nodes = BTreeMap::new();
+    for (caller, callee, _) in edges.keys() {
+        for symbol in [*caller, callee.as_str()] {
+            nodes.insert(symbol, names.get(symbol).copied());
+        }
+    }
+    nodes
+}
+
+fn kind_label(kind: &CallKind) -> String {
+    match kind {
+        CallKind::Direct => String::from("call"),
+        CallKind::FnPtr => String::from("fn ptr"),
+        CallKind::DropGlue => String::from("drop"),
+        CallKind::Virtual { idx } => format!("virtual #{idx}"),
+    }
+}
@@ -13,23 +13,23 @@ use crate::mk_graph::util::{
 
 impl SmirJson {
     /// Convert the MIR to D2 diagram format
-    pub fn to_d2_file(self) -> String {
-        let ctx = GraphContext::from_smir(&self);
+    pub fn to_d2_file(&self) -> String {
+        let ctx = GraphContext::from_smir(self);
         let mut output = String::new();
 
         output.push_str("direction: right\n\n");
         render_d2_allocs_legend(&ctx, &mut output);
 
-        for item in self.items {
-            match item.mono_item_kind {
+        for item in &self.items {
+            match &item.mono_item_kind {
                 MonoItemKind::MonoItemFn { name, body, .. } => {
-                    render_d2_function(&name, body.as_ref(), &ctx, &mut output);
+                    render_d2_function(name, body.as_ref(), &ctx, &mut output);
                 }
                 MonoItemKind::MonoItemGlobalAsm { asm } => {
-                    render_d2_asm(&asm, &mut output);
+                    render_d2_asm(asm, &mut output);
                 }
                 MonoItemKind::MonoItemStatic { name, .. } => {
-                    render_d2_static(&name, &mut output);
+                    render_d2_static(name, &mut output);
                 }
             }
         }
@@ -15,11 +15,10 @@ use crate::mk_graph::util::{block_name, is_unqualified, name_lines, short_name,
 
 impl SmirJson {
     /// Convert the MIR to DOT (Graphviz) format
-    pub fn to_dot_file(self) -> String {
+    pub fn to_dot_file(&self) -> String {
         let mut bytes = Vec::new();
 
-        // Build context BEFORE consuming self
-        let ctx = GraphContext::from_smir(&self);
+        let ctx = GraphContext::from_smir(self);
 
         {
             let mut writer = DotWriter::from(&mut bytes);
@@ -65,13 +64,13 @@ impl SmirJson {
                 }
             }
 
-            for item in self.items {
-                match item.mono_item_kind {
-                    MonoItemKind::MonoItemFn { name, body, id: _ } => {
+            for item in &self.items {
+                match &item.mono_item_kind {
+                    MonoItemKind::MonoItemFn { name, body, .. } => {
                         let mut c = graph.cluster();
-                        c.set_label(&name_lines(&name));
+                        c.set_label(&name_lines(name));
                         c.set_style(Style::Filled);
-                        if is_unqualified(&name) {
+                        if is_unqualified(name) {
                             c.set_color(Color::PaleGreen);
                         } else {
                             c.set_color(Color::LightGrey);
@@ -227,7 +226,7 @@ impl SmirJson {
                                 }
                             };
 
-                        if let Some(body) = &body {
+                        if let Some(body) = body {
                             process_blocks(&mut c, 0, &body.blocks);
                         } else {
                             c.node_auto().set_label("<empty body>");
@@ -287,16 +286,16 @@ impl SmirJson {
                                 }
                             };
 
-                        if let Some(ref body) = body {
+                        if let Some(body) = body {
                             add_call_edges(&mut graph, 0, &body.blocks);
                         }
                     }
                     MonoItemKind::MonoItemGlobalAsm { asm } => {
-                        let mut n = graph.node_named(short_name(&asm));
+                        let mut n = graph.node_named(short_name(asm));
                         n.set_label(&asm.lines().collect::<String>()[..]);
                     }
                     MonoItemKind::MonoItemStatic { name, .. } => {
-                        let mut n = graph.node_named(short_name(&name));
+                        let mut n = graph.node_named(short_name(name));
                         n.set_label(&name[..]);
                     }
                 }
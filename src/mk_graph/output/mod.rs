@@ -1,4 +1,5 @@
 //! Output format modules for MIR graph generation.
 
+pub mod calls;
 pub mod d2;
 pub mod dot;
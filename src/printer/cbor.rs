@@ -0,0 +1,451 @@
+//! CBOR (RFC 8949) encoding of the output, as an alternative to JSON.
+//!
+//! The driver can only use the `serde` build vendored into the sysroot, so
+//! rather than depending on a CBOR crate this is a small [`ser::Serializer`]
+//! following the same data model mapping as `ciborium` (and, apart from map
+//! keys, as `serde_json`): structs are maps keyed by field name, unit
+//! variants are strings, other enum variants are single-entry maps from the
+//! variant name to the contents, `None` and `()` are `null`. Map keys keep
+//! their CBOR type instead of being converted to strings as in JSON.
+//!
+//! Containers are written with the definite length serde announces; a
+//! mismatch is reported as an error rather than producing a corrupt file.
+
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+use crate::compat::serde::ser::{self, Serialize};
+
+const UINT: u8 = 0;
+const NEGINT: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const F32: u8 = 0xfa;
+const F64: u8 = 0xfb;
+const BREAK: u8 = 0xff;
+const INDEFINITE: u8 = 31;
+
+const TAG_POS_BIGNUM: u64 = 2;
+const TAG_NEG_BIGNUM: u64 = 3;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::Custom(msg) => io::Error::new(io::ErrorKind::InvalidData, msg),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+pub struct Serializer<W> {
+    writer: W,
+}
+
+impl<W: Write> Serializer<W> {
+    pub fn new(writer: W) -> Self {
+        Serializer { writer }
+    }
+
+    /// Write an item head: major type and argument, in the shortest form.
+    fn head(&mut self, major: u8, arg: u64) -> Result<()> {
+        let major = major << 5;
+        match arg {
+            0..=23 => self.writer.write_all(&[major | arg as u8])?,
+            24..=0xff => self.writer.write_all(&[major | 24, arg as u8])?,
+            0x100..=0xffff => {
+                self.writer.write_all(&[major | 25])?;
+                self.writer.write_all(&(arg as u16).to_be_bytes())?;
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.writer.write_all(&[major | 26])?;
+                self.writer.write_all(&(arg as u32).to_be_bytes())?;
+            }
+            _ => {
+                self.writer.write_all(&[major | 27])?;
+                self.writer.write_all(&arg.to_be_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn bignum(&mut self, tag: u64, v: u128) -> Result<()> {
+        self.head(TAG, tag)?;
+        let bytes = v.to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        self.head(BYTES, (bytes.len() - start) as u64)?;
+        Ok(self.writer.write_all(&bytes[start..])?)
+    }
+
+    fn container(&mut self, major: u8, len: Option<usize>) -> Result<Compound<'_, W>> {
+        match len {
+            Some(len) => self.head(major, len as u64)?,
+            None => self.writer.write_all(&[major << 5 | INDEFINITE])?,
+        }
+        Ok(Compound {
+            ser: self,
+            remaining: len,
+        })
+    }
+
+    /// Start a single-entry map from the variant name to its contents.
+    fn variant(&mut self, variant: &str) -> Result<()> {
+        self.head(MAP, 1)?;
+        ser::Serializer::serialize_str(self, variant)
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        Ok(self.writer.write_all(&[if v { TRUE } else { FALSE }])?)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        if v < 0 {
+            // CBOR stores -1 - v, which is the bitwise complement
+            self.head(NEGINT, !v as u64)
+        } else {
+            self.head(UINT, v as u64)
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) if v < 0 => match u64::try_from(!v) {
+                Ok(n) => self.head(NEGINT, n),
+                Err(_) => self.bignum(TAG_NEG_BIGNUM, !v as u128),
+            },
+            Err(_) => self.serialize_u128(v as u128),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.head(UINT, v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.head(UINT, v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.head(UINT, v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.head(UINT, v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        match u64::try_from(v) {
+            Ok(v) => self.head(UINT, v),
+            Err(_) => self.bignum(TAG_POS_BIGNUM, v),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.writer.write_all(&[F32])?;
+        Ok(self.writer.write_all(&v.to_be_bytes())?)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.writer.write_all(&[F64])?;
+        Ok(self.writer.write_all(&v.to_be_bytes())?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.head(TEXT, v.len() as u64)?;
+        Ok(self.writer.write_all(v.as_bytes())?)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.head(BYTES, v.len() as u64)?;
+        Ok(self.writer.write_all(v)?)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(self.writer.write_all(&[NULL])?)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.variant(variant)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a, W>> {
+        self.container(ARRAY, len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, W>> {
+        self.container(ARRAY, Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a, W>> {
+        self.container(ARRAY, Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>> {
+        self.variant(variant)?;
+        self.container(ARRAY, Some(len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a, W>> {
+        self.container(MAP, len)
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a, W>> {
+        self.container(MAP, Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>> {
+        self.variant(variant)?;
+        self.container(MAP, Some(len))
+    }
+}
+
+/// An array or map being written; `remaining` counts down the announced
+/// length, or is `None` for an indefinite-length container.
+pub struct Compound<'a, W> {
+    ser: &'a mut Serializer<W>,
+    remaining: Option<usize>,
+}
+
+impl<W: Write> Compound<'_, W> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.checked_sub(1).ok_or_else(|| {
+                Error::Custom("container has more elements than announced".into())
+            })?;
+        }
+        value.serialize(&mut *self.ser)
+    }
+
+    fn finish(self) -> Result<()> {
+        match self.remaining {
+            None => Ok(self.ser.writer.write_all(&[BREAK])?),
+            Some(0) => Ok(()),
+            Some(_) => Err(Error::Custom(
+                "container has fewer elements than announced".into(),
+            )),
+        }
+    }
+}
+
+impl<W: Write> ser::SerializeSeq for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTuple for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTupleStruct for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTupleVariant for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeMap for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeStruct for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.element(key)?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeStructVariant for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.element(key)?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
@@ -1,39 +1,56 @@
 //! Three-phase collection pipeline.
 //!
 //! - [`collect_items`]: phase 1, enumerates monomorphized items from rustc
+//!   (and, with `--smir-polymorphic`, [`collect_generic_items`] the generic
+//!   functions of the local crate)
 //! - [`collect_and_analyze_items`]: phase 2, walks bodies with [`BodyAnalyzer`],
-//!   discovering transitive items through unevaluated constants
-//! - [`assemble_smir`]: phase 3, pure data transformation into [`SmirJson`]
+//!   discovering transitive items through unevaluated constants (and, with
+//!   `--smir-upstream`, upstream callees)
+//! - [`assemble_smir`]: phase 3, builds the [`SmirJson`] from the collected
+//!   data, querying rustc only about types (layouts, ADT reprs and
+//!   discriminants, signatures), the target machine, and, with `STABLE_IDS`,
+//!   stable type and def path hashes
 //!
 //! The phase boundary between 2 and 3 is enforced structurally: [`Item`] does
 //! not carry a `MonoItem`, so phase 3 code cannot call `inst.body()` or
-//! otherwise re-enter rustc. `MonoItem` values live only in the phase 1+2
+//! otherwise collect more items. `MonoItem` values live only in the phase 1+2
 //! maps and are dropped before phase 3 begins.
 
+use crate::compat::bridge::{InstanceKindRepr, InstanceKindTag};
 use crate::compat::middle::ty::TyCtxt;
 use crate::compat::mono_collect::mono_collect;
+use crate::compat::serde_json;
 use crate::compat::stable_mir;
 
 use std::collections::{HashMap, HashSet};
 
 use stable_mir::mir::alloc::GlobalAlloc;
-use stable_mir::mir::mono::MonoItem;
+use stable_mir::mir::mono::{Instance, MonoItem};
 use stable_mir::mir::visit::MirVisitor;
 use stable_mir::ty::IndexedVal;
-use stable_mir::CrateDef;
+use stable_mir::visitor::Visitable;
+use stable_mir::{CrateDef, CrateItem, ItemKind};
 
-use super::items::{get_foreign_module_details, mk_item};
+use super::decode::Decoder;
+use super::filter::ItemFilter;
+use super::items::{get_foreign_module_details, mk_generic_item, mk_item, mk_upstream_item};
+use super::link_map::report_link_map_conflicts;
 use super::mir_visitor::{maybe_add_to_link_map, BodyAnalyzer, UnevalConstInfo};
 use super::schema::{
-    AllocInfo, AllocMap, CollectedCrate, DerivedInfo, Item, LinkMap, SmirJson, SmirJsonDebugInfo,
-    SpanMap,
+    AllocInfo, AllocMap, CollectedCrate, DerivedInfo, GenericItem, Item, LinkMap, LinkMapKey,
+    OutputFlags, SmirJson, SmirJsonDebugInfo, SmirJsonHeader, SpanMap, VTableInfo, VTableMap,
 };
+use super::stable_ids::StableIds;
 use super::ty_visitor::TyCollector;
 use super::types::mk_type_metadata;
+use super::upstream::UpstreamBodies;
 use super::util::take_any;
 
 use crate::compat::mono_collect::mono_item_name;
 
+/// Built items awaiting analysis, keyed by name.
+type ItemQueue = HashMap<String, (MonoItem, Item)>;
+
 /// Log a warning when a body was expected but missing.
 fn warn_missing_body(mono_item: &MonoItem) {
     match mono_item {
@@ -53,17 +70,137 @@ fn warn_missing_body(mono_item: &MonoItem) {
     }
 }
 
-fn collect_items(tcx: TyCtxt<'_>) -> HashMap<String, (MonoItem, Item)> {
-    // get initial set of mono_items
-    let items = mono_collect(tcx);
-    items
-        .iter()
-        .map(|item| {
-            let name = mono_item_name(tcx, item);
-            let (mono_item, built_item) = mk_item(tcx, item.clone(), name.clone());
-            (name, (mono_item, built_item))
+/// Phase 1: the mono items passing the path filter, built into items ready
+/// for analysis. With a reachability filter only the entry points are built;
+/// the other candidates are returned unbuilt, keyed by name, until a body
+/// reaches them. With upstream bodies, the names of the mono items that the
+/// path filter dropped are returned as well, so they are not added back as
+/// upstream callees.
+fn collect_items(
+    tcx: TyCtxt<'_>,
+    flags: &OutputFlags,
+) -> (ItemQueue, HashMap<String, MonoItem>, HashSet<String>) {
+    let filter = &flags.filter;
+    let mut initial = HashMap::new();
+    let mut dormant = HashMap::new();
+    let mut excluded = HashSet::new();
+    let mut entries_found = HashSet::new();
+    for item in mono_collect(tcx) {
+        if !filter.matches(&item) {
+            if flags.upstream.is_some() {
+                excluded.insert(mono_item_name(tcx, &item));
+            }
+            continue;
+        }
+        let name = mono_item_name(tcx, &item);
+        let entry = filter.entry_point(&item);
+        if filter.reachable_from.is_empty() || entry.is_some() {
+            entries_found.extend(entry);
+            let (mono_item, built_item) = mk_item(tcx, item, name.clone(), flags.debug);
+            initial.insert(name, (mono_item, built_item));
+        } else {
+            dormant.insert(name, item);
+        }
+    }
+    for entry in &filter.reachable_from {
+        if !entries_found.contains(entry.as_str()) {
+            eprintln!("warning: no mono item for entry point `{entry}`");
+        }
+    }
+    (initial, dormant, excluded)
+}
+
+/// Phase 1, polymorphic part: the generic functions (and closures) of the
+/// local crate that pass the path filter, if the `polymorphic` flag is set.
+/// These are never instantiated as such, so `mono_collect` does not see them.
+fn collect_generic_items(tcx: TyCtxt<'_>, flags: &OutputFlags) -> Vec<GenericItem> {
+    if !flags.polymorphic {
+        return Vec::new();
+    }
+    stable_mir::all_local_items()
+        .into_iter()
+        .filter(|item| {
+            matches!(item.kind(), ItemKind::Fn)
+                && item.requires_monomorphization()
+                && flags.filter.matches_path(&item.name())
         })
-        .collect::<HashMap<_, _>>()
+        .map(|item| mk_generic_item(tcx, item))
+        .collect()
+}
+
+/// Upstream callees found by the bodies of the current depth, which are
+/// added once every item of that depth has been analyzed.
+struct UpstreamFrontier<'a> {
+    bodies: &'a UpstreamBodies,
+    /// The path globs, which callees have to pass as well.
+    filter: &'a ItemFilter,
+    /// Depth of the items being analyzed; 0 for the local crate's items.
+    depth: usize,
+    /// Names of the local mono items that the path filter dropped.
+    excluded: HashSet<String>,
+    next: HashMap<String, (Instance, String)>,
+}
+
+impl UpstreamFrontier<'_> {
+    /// Record `item` for the next depth if it is an admitted upstream
+    /// function that passes the path globs and is not already part of the
+    /// output.
+    fn offer(&mut self, name: String, item: &MonoItem, known_names: &HashSet<String>) {
+        if known_names.contains(&name)
+            || self.excluded.contains(&name)
+            || self.next.contains_key(&name)
+            || !self.bodies.admits_depth(self.depth + 1)
+            || !self.filter.matches(item)
+        {
+            return;
+        }
+        if let Some(callee) = self.bodies.upstream_fn(item) {
+            self.next.insert(name, callee);
+        }
+    }
+
+    /// Move to the next depth, adding the recorded callees that have a body
+    /// to the work queue.
+    fn advance(
+        &mut self,
+        tcx: TyCtxt<'_>,
+        known_names: &mut HashSet<String>,
+        pending: &mut ItemQueue,
+        flags: &OutputFlags,
+    ) {
+        self.depth += 1;
+        for (name, (inst, krate)) in self.next.drain() {
+            known_names.insert(name.clone());
+            if let Some(new_entry) = mk_upstream_item(tcx, inst, name.clone(), krate, flags.debug) {
+                debug_log_println!("Adding upstream item: {}", name);
+                pending.insert(name, new_entry);
+            }
+        }
+    }
+}
+
+/// Move the items that a body reached out of `dormant` into the work queue,
+/// and offer the others to the upstream frontier.
+fn enqueue_reached(
+    tcx: TyCtxt<'_>,
+    reached: Vec<MonoItem>,
+    dormant: &mut HashMap<String, MonoItem>,
+    mut upstream: Option<&mut UpstreamFrontier>,
+    known_names: &mut HashSet<String>,
+    pending: &mut ItemQueue,
+    flags: &OutputFlags,
+) {
+    for item in reached {
+        let name = mono_item_name(tcx, &item);
+        if let Some(item) = dormant.remove(&name) {
+            debug_log_println!("Adding reachable item: {}", name);
+            let new_entry = mk_item(tcx, item, name.clone(), flags.debug);
+            pending.insert(name.clone(), new_entry);
+            known_names.insert(name);
+        } else if let Some(upstream) = upstream.as_deref_mut() {
+            upstream.offer(name, &item, known_names);
+        }
+    }
 }
 
 /// Enqueue newly discovered unevaluated-const items into the fixpoint work queue.
@@ -72,22 +209,53 @@ fn collect_items(tcx: TyCtxt<'_>) -> HashMap<String, (MonoItem, Item)> {
 fn enqueue_unevaluated_consts(
     tcx: TyCtxt<'_>,
     discovered: Vec<UnevalConstInfo>,
+    dormant: &mut HashMap<String, MonoItem>,
     known_names: &mut HashSet<String>,
-    pending: &mut HashMap<String, (MonoItem, Item)>,
+    pending: &mut ItemQueue,
     unevaluated_consts: &mut HashMap<stable_mir::ty::ConstDef, String>,
+    flags: &OutputFlags,
 ) {
     for info in discovered {
         if known_names.contains(&info.item_name) || pending.contains_key(&info.item_name) {
             continue;
         }
         debug_log_println!("Adding unevaluated const body for: {}", info.item_name);
+        dormant.remove(&info.item_name);
         unevaluated_consts.insert(info.const_def, info.item_name.clone());
-        let new_entry = mk_item(tcx, info.mono_item, info.item_name.clone());
+        let new_entry = mk_item(tcx, info.mono_item, info.item_name.clone(), flags.debug);
         pending.insert(info.item_name.clone(), new_entry);
         known_names.insert(info.item_name);
     }
 }
 
+/// Enqueue the shims that a body calls and that no item provides, so that
+/// every shim a terminator refers to has its body in the output. Like
+/// unevaluated constants, these are kept whatever the item filter: they are
+/// glue the kept bodies need, not items of their own. Shims instantiated
+/// upstream (e.g. drop glue shared by a dependency) are not mono items of
+/// the crate, but their bodies are built locally.
+fn enqueue_called_shims(
+    tcx: TyCtxt<'_>,
+    shims: Vec<Instance>,
+    dormant: &mut HashMap<String, MonoItem>,
+    known_names: &mut HashSet<String>,
+    pending: &mut ItemQueue,
+    flags: &OutputFlags,
+) {
+    for inst in shims {
+        let item = MonoItem::Fn(inst);
+        let name = mono_item_name(tcx, &item);
+        if known_names.contains(&name) {
+            continue;
+        }
+        debug_log_println!("Adding called shim: {}", name);
+        dormant.remove(&name);
+        let new_entry = mk_item(tcx, item, name.clone(), flags.debug);
+        pending.insert(name.clone(), new_entry);
+        known_names.insert(name);
+    }
+}
+
 /// Collect all mono items and analyze their bodies in a single pass per body.
 ///
 /// Each body is walked exactly once. The fixpoint loop handles transitive
@@ -96,22 +264,50 @@ fn enqueue_unevaluated_consts(
 /// (calling `inst.body()` exactly once) and adds it to the work queue. The
 /// `MonoItem` half is used for link-map registration and diagnostics during
 /// this phase, then dropped; only the `Item` survives into `CollectedCrate`.
+///
+/// Shims that a body calls (or drops with) are added the same way when no
+/// mono item provides them.
+///
+/// Upstream callees are added one depth level at a time, once the work queue
+/// is empty. Generic bodies are walked last, in polymorphic mode: they
+/// contribute types, allocations and spans, but nothing that needs an
+/// instance.
 fn collect_and_analyze_items(
     tcx: TyCtxt<'_>,
-    initial_items: HashMap<String, (MonoItem, Item)>,
+    initial_items: ItemQueue,
+    mut dormant: HashMap<String, MonoItem>,
+    excluded: HashSet<String>,
+    generic_items: Vec<GenericItem>,
+    flags: &OutputFlags,
 ) -> (CollectedCrate, DerivedInfo) {
     let mut calls_map: LinkMap = HashMap::new();
     let mut visited_allocs = AllocMap::new();
     let mut ty_visitor = TyCollector::new(tcx);
     let mut span_map: SpanMap = HashMap::new();
+    let mut vtables: VTableMap = HashMap::new();
+    let mut call_edges = Vec::new();
+    let mut link_conflicts = Vec::new();
     let mut unevaluated_consts: HashMap<stable_mir::ty::ConstDef, String> = HashMap::new();
 
     let mut known_names: HashSet<String> = initial_items.keys().cloned().collect();
-    let mut pending: HashMap<String, (MonoItem, Item)> = initial_items;
+    let mut pending: ItemQueue = initial_items;
     let mut all_items: Vec<Item> = Vec::new();
+    let mut upstream = flags.upstream.as_ref().map(|bodies| UpstreamFrontier {
+        bodies,
+        filter: &flags.filter,
+        depth: 0,
+        excluded,
+        next: HashMap::new(),
+    });
 
-    while let Some((_name, (mono_item, item))) = take_any(&mut pending) {
-        maybe_add_to_link_map(tcx, &mono_item, &mut calls_map);
+    // Once the queue is drained, the next depth of upstream callees refills it.
+    while let Some((_name, (mono_item, item))) = take_any(&mut pending).or_else(|| {
+        upstream
+            .as_mut()?
+            .advance(tcx, &mut known_names, &mut pending, flags);
+        take_any(&mut pending)
+    }) {
+        maybe_add_to_link_map(tcx, &mono_item, &mut calls_map, &mut link_conflicts, flags);
 
         let Some((body, locals)) = item.body_and_locals() else {
             warn_missing_body(&mono_item);
@@ -120,28 +316,97 @@ fn collect_and_analyze_items(
         };
 
         let mut new_unevaluated = Vec::new();
+        let mut called_shims = Vec::new();
+        let mut reached = Vec::new();
+        let track_reached = !dormant.is_empty() || upstream.is_some();
         BodyAnalyzer {
             tcx,
             locals,
             link_map: &mut calls_map,
+            link_conflicts: &mut link_conflicts,
             visited_allocs: &mut visited_allocs,
             ty_visitor: &mut ty_visitor,
             spans: &mut span_map,
+            vtables: &mut vtables,
+            caller: &item.symbol_name,
+            call_edges: &mut call_edges,
+            link_inst: flags.link_inst,
+            polymorphic: false,
+            called_shims: &mut called_shims,
             new_unevaluated: &mut new_unevaluated,
+            reached: track_reached.then_some(&mut reached),
         }
         .visit_body(body);
+        if let Some(fn_abi) = item.fn_abi() {
+            fn_abi.tys().visit(&mut ty_visitor);
+        }
 
         enqueue_unevaluated_consts(
             tcx,
             new_unevaluated,
+            &mut dormant,
             &mut known_names,
             &mut pending,
             &mut unevaluated_consts,
+            flags,
+        );
+        enqueue_reached(
+            tcx,
+            reached,
+            &mut dormant,
+            upstream.as_mut(),
+            &mut known_names,
+            &mut pending,
+            flags,
+        );
+        enqueue_called_shims(
+            tcx,
+            called_shims,
+            &mut dormant,
+            &mut known_names,
+            &mut pending,
+            flags,
         );
 
         all_items.push(item);
     }
 
+    for item in &generic_items {
+        let late_bound_spans = item
+            .generics
+            .iter()
+            .filter_map(|g| g.has_late_bound_regions.as_ref());
+        for span in item
+            .predicates
+            .iter()
+            .map(|(_, span)| span)
+            .chain(late_bound_spans)
+        {
+            span_map.insert(
+                span.to_index(),
+                crate::compat::spans::resolve_span(tcx, span),
+            );
+        }
+        BodyAnalyzer {
+            tcx,
+            locals: item.body.locals(),
+            link_map: &mut calls_map,
+            link_conflicts: &mut link_conflicts,
+            visited_allocs: &mut visited_allocs,
+            ty_visitor: &mut ty_visitor,
+            spans: &mut span_map,
+            vtables: &mut vtables,
+            caller: &item.name,
+            call_edges: &mut call_edges,
+            link_inst: flags.link_inst,
+            polymorphic: true,
+            called_shims: &mut Vec::new(),
+            new_unevaluated: &mut Vec::new(),
+            reached: None,
+        }
+        .visit_body(&item.body);
+    }
+
     if !ty_visitor.layout_panics.is_empty() {
         eprintln!(
             "warning: {} type layout(s) could not be computed (rustc panicked):",
@@ -151,10 +416,12 @@ fn collect_and_analyze_items(
             eprintln!("  type {:?}: {}", panic.ty, panic.message);
         }
     }
+    report_link_map_conflicts(tcx, &calls_map, &mut link_conflicts);
 
     (
         CollectedCrate {
             items: all_items,
+            generic_items,
             unevaluated_consts,
         },
         DerivedInfo {
@@ -162,6 +429,9 @@ fn collect_and_analyze_items(
             allocs: visited_allocs,
             types: ty_visitor.types,
             spans: span_map,
+            vtables,
+            call_edges,
+            link_conflicts,
         },
     )
 }
@@ -205,12 +475,74 @@ fn alloc_bytes(info: &AllocInfo) -> &[Option<u8>] {
     }
 }
 
+/// The variant of an instance kind and the items and types it refers to, as
+/// text.
+fn instance_kind_key(kind: &InstanceKindRepr) -> (InstanceKindTag, String) {
+    use InstanceKindRepr::*;
+    let name = |def: &stable_mir::DefId| CrateItem(*def).name();
+    let refs = match kind {
+        Item(def) | Intrinsic(def) | VTableShim(def) | ThreadLocalShim(def) => name(def),
+        ReifyShim(def, reason) => format!("{} {reason:?}", name(def)),
+        FnPtrShim(def, ty) | CloneShim(def, ty) | FnPtrAddrShim(def, ty) => {
+            format!("{} {ty}", name(def))
+        }
+        DropGlue(def, ty) | AsyncDropGlueCtorShim(def, ty) => match ty {
+            Some(ty) => format!("{} {ty}", name(def)),
+            None => name(def),
+        },
+        Virtual(def, idx) => format!("{} {idx}", name(def)),
+        ClosureOnceShim {
+            call_once: def,
+            track_caller: flag,
+        }
+        | ConstructCoroutineInClosureShim {
+            coroutine_closure_def_id: def,
+            receiver_by_ref: flag,
+        } => format!("{} {flag}", name(def)),
+    };
+    (kind.tag(), refs)
+}
+
+/// A link map key as written with stable IDs, which identifies it across
+/// runs and crates.
+fn stable_key(ids: &StableIds, key: &LinkMapKey) -> Vec<u8> {
+    let mut out = Vec::new();
+    ids.serialize(key, &mut serde_json::Serializer::new(&mut out))
+        .expect("serializing to memory cannot fail");
+    out
+}
+
+/// The principal trait of a vtable and its generic arguments, as text.
+fn vtable_trait_key(vtable: &VTableInfo) -> Option<(String, Vec<String>)> {
+    use stable_mir::ty::GenericArgKind;
+    let trait_ref = &vtable.trait_ref.as_ref()?.value;
+    let args = trait_ref
+        .generic_args
+        .0
+        .iter()
+        .map(|arg| match arg {
+            GenericArgKind::Type(ty) => format!("{ty}"),
+            GenericArgKind::Lifetime(_) => String::from("'_"),
+            GenericArgKind::Const(c) => format!("{:?}", c.kind()),
+        })
+        .collect();
+    Some((trait_ref.def_id.name(), args))
+}
+
 /// Phase 3: Assemble the final SmirJson from collected and derived data.
-/// This is a pure data transformation with no inst.body() calls.
-fn assemble_smir(tcx: TyCtxt<'_>, collected: CollectedCrate, derived: DerivedInfo) -> SmirJson {
+/// Makes no inst.body() calls; `tcx` is used for the type metadata, the
+/// stable IDs and the crate ID, and allocations are decoded with the target's
+/// machine info.
+fn assemble_smir(
+    tcx: TyCtxt<'_>,
+    collected: CollectedCrate,
+    derived: DerivedInfo,
+    flags: &OutputFlags,
+) -> SmirJson {
     let local_crate = stable_mir::local_crate();
     let CollectedCrate {
-        mut items,
+        items,
+        generic_items,
         unevaluated_consts,
     } = collected;
     let DerivedInfo {
@@ -218,6 +550,9 @@ fn assemble_smir(tcx: TyCtxt<'_>, collected: CollectedCrate, derived: DerivedInf
         allocs: visited_allocs,
         types: visited_tys,
         spans: span_map,
+        vtables,
+        call_edges,
+        link_conflicts,
     } = derived;
 
     // Verify alloc coherence: no duplicate AllocIds, and every AllocId
@@ -225,13 +560,14 @@ fn assemble_smir(tcx: TyCtxt<'_>, collected: CollectedCrate, derived: DerivedInf
     #[cfg(debug_assertions)]
     visited_allocs.verify_coherence(&items);
 
-    let debug: Option<SmirJsonDebugInfo> = if super::debug_enabled() {
+    let debug: Option<SmirJsonDebugInfo> = if flags.debug {
         let fn_sources = calls
             .iter()
-            .map(|(k, (source, _))| (k.clone(), source.clone()))
+            .map(|(k, (source, ..))| (k.clone(), source.clone()))
             .collect::<Vec<_>>();
         Some(SmirJsonDebugInfo {
             fn_sources,
+            link_conflicts,
             types: visited_tys.clone(),
             foreign_modules: get_foreign_module_details(),
         })
@@ -239,75 +575,156 @@ fn assemble_smir(tcx: TyCtxt<'_>, collected: CollectedCrate, derived: DerivedInf
         None
     };
 
-    let mut functions = calls
+    let functions = calls
         .into_iter()
-        .map(|(k, (_, name))| (k, name))
-        .collect::<Vec<_>>();
-    let mut allocs = visited_allocs
-        .into_entries()
-        .map(|(alloc_id, (ty, global_alloc))| AllocInfo::new(alloc_id, ty, global_alloc))
+        .map(|(k, (_, name, tag))| (k, name, tag))
         .collect::<Vec<_>>();
     let crate_id = crate::compat::types::local_crate_id(tcx);
 
-    let mut types = visited_tys
+    let types = visited_tys
         .into_iter()
         .filter_map(|(k, (t, l))| mk_type_metadata(tcx, k, t, l))
         .collect::<Vec<_>>();
 
-    let mut spans = span_map.into_iter().collect::<Vec<_>>();
+    let machine = stable_mir::target::MachineInfo::target();
+    let decoder = Decoder::new(&types, &machine);
+    let allocs = visited_allocs
+        .into_entries()
+        .map(|(alloc_id, (ty, global_alloc))| {
+            let decoded = ty.and_then(|ty| decoder.decode_alloc(ty, &global_alloc));
+            AllocInfo::new(alloc_id, ty, global_alloc, decoded)
+        })
+        .collect::<Vec<_>>();
+
+    let spans = span_map.into_iter().collect::<Vec<_>>();
+    let vtables = vtables.into_values().collect::<Vec<_>>();
+
+    let mut uneval_consts: Vec<_> = unevaluated_consts.into_iter().collect();
+    uneval_consts.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let mut smir = SmirJson {
+        header: SmirJsonHeader::new(flags),
+        name: local_crate.name,
+        crate_id,
+        allocs,
+        functions,
+        vtables,
+        calls: call_edges,
+        uneval_consts,
+        items,
+        generic_items,
+        types,
+        spans,
+        debug,
+        machine,
+        stable_ids: None,
+    };
+    if flags.stable_ids {
+        smir.stable_ids = Some(StableIds::build(tcx, &smir));
+    }
+    sort_smir(&mut smir);
+    smir
+}
+
+/// Sort output vectors by content-derived keys for deterministic output.
+///
+/// Ty's Display impl (ty_pretty) should be injective for monomorphized types,
+/// but we use a tiebreaker just in case two distinct types produce the same
+/// display string: the interned index by default, or the stable ID when
+/// `STABLE_IDS` is set. In the latter mode, entries that collapse onto the
+/// same stable ID (e.g. two allocations with identical content) are
+/// deduplicated, since they are indistinguishable in the output.
+fn sort_smir(smir: &mut SmirJson) {
+    let ids = smir.stable_ids.as_ref();
+    let ty_key = |ty: stable_mir::ty::Ty| match ids {
+        Some(ids) => ids.ty(ty),
+        None => ty.to_index() as u64,
+    };
 
-    // sort output vectors by content-derived keys for deterministic output.
-    // Ty's Display impl (ty_pretty) should be injective for monomorphized types,
-    // but we use the interned index as a tiebreaker just in case two distinct
-    // types produce the same display string.
-    allocs.sort_by(|a, b| {
+    smir.allocs.sort_by(|a, b| {
         alloc_sort_tag(a)
             .cmp(alloc_sort_tag(b))
             .then_with(|| alloc_content_key(a).cmp(&alloc_content_key(b)))
             .then_with(|| alloc_bytes(a).cmp(alloc_bytes(b)))
+            .then_with(|| {
+                ids.map(|ids| ids.alloc(a.alloc_id()))
+                    .cmp(&ids.map(|ids| ids.alloc(b.alloc_id())))
+            })
     });
-    functions.sort_by(|a, b| {
+    smir.functions.sort_by(|a, b| {
         format!("{}", a.0 .0)
             .cmp(&format!("{}", b.0 .0))
             .then_with(|| {
-                let a_kind = a.0 .1.as_ref().map(|k| format!("{k}"));
-                let b_kind = b.0 .1.as_ref().map(|k| format!("{k}"));
+                let a_kind = a.0 .1.as_ref().map(instance_kind_key);
+                let b_kind = b.0 .1.as_ref().map(instance_kind_key);
                 a_kind.cmp(&b_kind)
             })
-            .then_with(|| a.0 .0.to_index().cmp(&b.0 .0.to_index()))
+            .then_with(|| ty_key(a.0 .0).cmp(&ty_key(b.0 .0)))
+            .then_with(|| {
+                ids.map(|ids| stable_key(ids, &a.0))
+                    .cmp(&ids.map(|ids| stable_key(ids, &b.0)))
+            })
     });
-    items.sort();
-    types.sort_by(|a, b| {
+    smir.vtables.sort_by(|a, b| {
+        format!("{}", a.ty)
+            .cmp(&format!("{}", b.ty))
+            .then_with(|| vtable_trait_key(a).cmp(&vtable_trait_key(b)))
+            .then_with(|| ty_key(a.ty).cmp(&ty_key(b.ty)))
+    });
+    let span_data = smir
+        .spans
+        .iter()
+        .map(|(span, data)| (*span, data))
+        .collect::<HashMap<_, _>>();
+    smir.calls.sort_by(|a, b| {
+        a.caller
+            .cmp(&b.caller)
+            .then_with(|| {
+                span_data
+                    .get(&a.span.to_index())
+                    .cmp(&span_data.get(&b.span.to_index()))
+            })
+            .then_with(|| a.callee.cmp(&b.callee))
+            .then_with(|| a.kind.cmp(&b.kind))
+    });
+    smir.calls.dedup();
+    smir.items.sort();
+    smir.generic_items.sort_by(|a, b| a.name.cmp(&b.name));
+    smir.types.sort_by(|a, b| {
         format!("{}", a.0)
             .cmp(&format!("{}", b.0))
-            .then_with(|| a.0.to_index().cmp(&b.0.to_index()))
+            .then_with(|| ty_key(a.0).cmp(&ty_key(b.0)))
+    });
+    smir.spans.sort_by(|a, b| {
+        a.1.cmp(&b.1).then_with(|| {
+            ids.map(|ids| ids.span(a.0))
+                .cmp(&ids.map(|ids| ids.span(b.0)))
+        })
     });
-    spans.sort_by(|a, b| a.1.cmp(&b.1));
-
-    let mut uneval_consts: Vec<_> = unevaluated_consts.into_iter().collect();
-    uneval_consts.sort_by(|a, b| a.1.cmp(&b.1));
 
-    SmirJson {
-        name: local_crate.name,
-        crate_id,
-        allocs,
-        functions,
-        uneval_consts,
-        items,
-        types,
-        spans,
-        debug,
-        machine: stable_mir::target::MachineInfo::target(),
+    if let Some(ids) = ids {
+        let mut seen = HashSet::new();
+        smir.allocs.retain(|a| seen.insert(ids.alloc(a.alloc_id())));
+        let mut seen = HashSet::new();
+        smir.functions
+            .retain(|(k, ..)| seen.insert(stable_key(ids, k)));
+        let mut seen = HashSet::new();
+        smir.types.retain(|(ty, _)| seen.insert(ids.ty(*ty)));
+        let mut seen = HashSet::new();
+        smir.spans.retain(|(span, _)| seen.insert(ids.span(*span)));
     }
 }
 
-pub fn collect_smir(tcx: TyCtxt<'_>) -> SmirJson {
+pub fn collect_smir(tcx: TyCtxt<'_>, flags: &OutputFlags) -> SmirJson {
     // Phase 1+2: Collect all mono items from rustc and analyze their bodies
     // in a single pass. Each body is walked exactly once. Transitive item
-    // discovery (unevaluated constants) is handled by a fixpoint loop.
-    let initial_items = collect_items(tcx);
-    let (collected, derived) = collect_and_analyze_items(tcx, initial_items);
+    // discovery (unevaluated constants, reachable items when filtering, and
+    // upstream callees) is handled by a fixpoint loop.
+    let (initial_items, dormant, excluded) = collect_items(tcx, flags);
+    let generic_items = collect_generic_items(tcx, flags);
+    let (collected, derived) =
+        collect_and_analyze_items(tcx, initial_items, dormant, excluded, generic_items, flags);
 
-    // Phase 3: Assemble the final output (pure data transformation)
-    assemble_smir(tcx, collected, derived)
+    // Phase 3: Assemble the final output (no further bodies are walked)
+    assemble_smir(tcx, collected, derived, flags)
 }
@@ -0,0 +1,343 @@
+//! Decoding the bytes of memory allocations into typed values.
+//!
+//! [`Decoder`] reads an allocation as a value of its type, following the
+//! layouts recorded in the final `types` table rather than querying the
+//! compiler: struct, tuple and closure fields are read at the offsets of the
+//! type's `FieldsShape`, enum variants are selected by their tag as described
+//! by `enum_layout`, and pointers take their target from the allocation's
+//! provenance. This makes the result exactly what a consumer would get by
+//! interpreting the output itself.
+//!
+//! Unsized values (`str`, slices) extend to the end of the allocation, which
+//! is where a pointer's metadata would end them. A part that cannot be read
+//! as a value of its type is given as its raw bytes; only a type without any
+//! layout information makes the whole allocation undecodable.
+
+use crate::compat::stable_mir;
+
+use std::collections::HashMap;
+
+use stable_mir::abi::{FieldsShape, LayoutShape, TagEncoding};
+use stable_mir::mir::alloc::{AllocId, GlobalAlloc};
+use stable_mir::target::{Endian, MachineInfo};
+use stable_mir::ty::{Allocation, FloatTy, IndexedVal, RigidTy, Ty};
+
+use super::schema::{DecodedValue, EnumLayout, TypeMetadata};
+
+pub(super) struct Decoder<'a> {
+    types: HashMap<Ty, &'a TypeMetadata>,
+    pointer_size: usize,
+    endian: Endian,
+}
+
+impl<'a> Decoder<'a> {
+    pub(super) fn new(types: &'a [(Ty, TypeMetadata)], machine: &MachineInfo) -> Self {
+        Decoder {
+            types: types.iter().map(|(ty, metadata)| (*ty, metadata)).collect(),
+            pointer_size: machine.pointer_width.bytes(),
+            endian: machine.endian,
+        }
+    }
+
+    /// Decode a memory allocation as a value of type `ty`.
+    pub(super) fn decode_alloc(&self, ty: Ty, global_alloc: &GlobalAlloc) -> Option<DecodedValue> {
+        match global_alloc {
+            GlobalAlloc::Memory(alloc) => self.decode(alloc, ty, 0),
+            _ => None,
+        }
+    }
+
+    /// The size of a sized type, if known.
+    fn size_of(&self, ty: Ty) -> Option<usize> {
+        match self.types.get(&ty)? {
+            TypeMetadata::PrimitiveType(rigid) => primitive_size(rigid),
+            metadata => layout_of(metadata)
+                .filter(|layout| layout.is_sized())
+                .map(|layout| layout.size.bytes()),
+        }
+    }
+
+    fn decode(&self, alloc: &Allocation, ty: Ty, offset: usize) -> Option<DecodedValue> {
+        let metadata = *self.types.get(&ty)?;
+        let value = match metadata {
+            TypeMetadata::PrimitiveType(rigid) => self.decode_primitive(alloc, rigid, offset),
+            TypeMetadata::EnumType {
+                fields,
+                enum_layout: Some(enum_layout),
+                ..
+            } => self.decode_enum(alloc, fields, enum_layout, offset),
+            TypeMetadata::StructType {
+                fields,
+                layout: Some(layout),
+                ..
+            }
+            | TypeMetadata::TupleType {
+                types: fields,
+                layout: Some(layout),
+            }
+            | TypeMetadata::ClosureType {
+                upvar_tys: fields,
+                layout: Some(layout),
+                ..
+            } => self
+                .decode_fields(alloc, fields, &layout.fields, offset)
+                .map(DecodedValue::Aggregate),
+            TypeMetadata::ArrayType {
+                elem_type,
+                size: Some(_),
+                layout: Some(layout),
+            } => match layout.fields {
+                FieldsShape::Array { stride, count } => {
+                    self.decode_array(alloc, *elem_type, offset, stride.bytes(), count as usize)
+                }
+                _ => None,
+            },
+            // a slice extends to the end of the allocation
+            TypeMetadata::ArrayType {
+                elem_type,
+                size: None,
+                ..
+            } => match self.size_of(*elem_type)? {
+                0 => None,
+                stride => {
+                    let count = alloc.bytes.len().saturating_sub(offset) / stride;
+                    self.decode_array(alloc, *elem_type, offset, stride, count)
+                }
+            },
+            TypeMetadata::PtrType {
+                layout: Some(layout),
+                ..
+            }
+            | TypeMetadata::RefType {
+                layout: Some(layout),
+                ..
+            }
+            | TypeMetadata::FnSigType {
+                layout: Some(layout),
+                ..
+            } if layout.size.bytes() > 0 => self.decode_ptr(alloc, offset, layout.size.bytes()),
+            // a function definition, which has no data
+            TypeMetadata::FnSigType { .. } => Some(DecodedValue::Aggregate(Vec::new())),
+            _ => None,
+        };
+        // fall back to the raw bytes of anything of known size
+        value.or_else(|| {
+            let size = self.size_of(ty)?;
+            Some(DecodedValue::Bytes(bytes(alloc, offset, size)?.to_vec()))
+        })
+    }
+
+    fn decode_primitive(
+        &self,
+        alloc: &Allocation,
+        rigid: &RigidTy,
+        offset: usize,
+    ) -> Option<DecodedValue> {
+        if let RigidTy::Str = rigid {
+            let bytes = alloc.bytes.get(offset..)?;
+            let bytes = bytes.iter().copied().collect::<Option<Vec<u8>>>()?;
+            return String::from_utf8(bytes).ok().map(DecodedValue::Str);
+        }
+        let size = primitive_size(rigid)?;
+        let value = read_uint(alloc, offset, size, self.endian)?;
+        match rigid {
+            RigidTy::Bool if value <= 1 => Some(DecodedValue::Bool(value == 1)),
+            RigidTy::Char => char::from_u32(value as u32).map(DecodedValue::Char),
+            RigidTy::Int(_) => {
+                let shift = 128 - 8 * size as u32;
+                Some(DecodedValue::Int((value << shift) as i128 >> shift))
+            }
+            RigidTy::Uint(_) => Some(DecodedValue::Uint(value)),
+            RigidTy::Float(FloatTy::F32) => Some(DecodedValue::Float(
+                f32::from_bits(value as u32).to_string(),
+            )),
+            RigidTy::Float(FloatTy::F64) => Some(DecodedValue::Float(
+                f64::from_bits(value as u64).to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn decode_enum(
+        &self,
+        alloc: &Allocation,
+        fields: &[Vec<Ty>],
+        enum_layout: &EnumLayout,
+        offset: usize,
+    ) -> Option<DecodedValue> {
+        let variant = read_variant(alloc, offset, enum_layout, self.endian)?;
+        let offsets = &enum_layout.variants[variant].as_ref()?.field_offsets;
+        let fields = fields[variant]
+            .iter()
+            .zip(offsets)
+            .map(|(ty, field_offset)| self.decode(alloc, *ty, offset + field_offset))
+            .collect::<Option<Vec<_>>>()?;
+        Some(DecodedValue::Enum { variant, fields })
+    }
+
+    fn decode_fields(
+        &self,
+        alloc: &Allocation,
+        fields: &[Ty],
+        shape: &FieldsShape,
+        offset: usize,
+    ) -> Option<Vec<DecodedValue>> {
+        let FieldsShape::Arbitrary { offsets } = shape else {
+            return None;
+        };
+        fields
+            .iter()
+            .zip(offsets)
+            .map(|(ty, field_offset)| self.decode(alloc, *ty, offset + field_offset.bytes()))
+            .collect()
+    }
+
+    fn decode_array(
+        &self,
+        alloc: &Allocation,
+        elem_type: Ty,
+        offset: usize,
+        stride: usize,
+        count: usize,
+    ) -> Option<DecodedValue> {
+        (0..count)
+            .map(|i| self.decode(alloc, elem_type, offset + i * stride))
+            .collect::<Option<Vec<_>>>()
+            .map(DecodedValue::Array)
+    }
+
+    /// A thin pointer, or a wide one followed by its metadata.
+    fn decode_ptr(&self, alloc: &Allocation, offset: usize, size: usize) -> Option<DecodedValue> {
+        let metadata = if size > self.pointer_size {
+            let metadata = self.decode_thin_ptr(alloc, offset + self.pointer_size)?;
+            // metadata without provenance is a length
+            Some(Box::new(match metadata {
+                DecodedValue::Ptr {
+                    alloc_id: None,
+                    offset,
+                    ..
+                } => DecodedValue::Uint(offset.into()),
+                ptr => ptr,
+            }))
+        } else {
+            None
+        };
+        match self.decode_thin_ptr(alloc, offset)? {
+            DecodedValue::Ptr {
+                alloc_id, offset, ..
+            } => Some(DecodedValue::Ptr {
+                alloc_id,
+                offset,
+                metadata,
+            }),
+            _ => None,
+        }
+    }
+
+    fn decode_thin_ptr(&self, alloc: &Allocation, offset: usize) -> Option<DecodedValue> {
+        let address = read_uint(alloc, offset, self.pointer_size, self.endian)?;
+        Some(DecodedValue::Ptr {
+            alloc_id: provenance(alloc, offset),
+            offset: address as u64,
+            metadata: None,
+        })
+    }
+}
+
+/// The variant of the enum stored at `offset` in `alloc`, as selected by its
+/// tag (or the only variant, if there is no tag).
+pub(super) fn read_variant(
+    alloc: &Allocation,
+    offset: usize,
+    enum_layout: &EnumLayout,
+    endian: Endian,
+) -> Option<usize> {
+    let is_variant = |value: Option<u128>| {
+        enum_layout
+            .variants
+            .iter()
+            .position(|variant| variant.as_ref().is_some_and(|v| v.tag_value == value))
+    };
+    let Some(tag) = &enum_layout.tag else {
+        return is_variant(None);
+    };
+    let value = read_uint(alloc, offset + tag.offset, tag.size, endian)?;
+    // a pointer into an allocation is never one of the niche values
+    let value = provenance(alloc, offset + tag.offset)
+        .is_none()
+        .then_some(value);
+    match (
+        value.and_then(|value| is_variant(Some(value))),
+        &tag.encoding,
+    ) {
+        (Some(variant), _) => Some(variant),
+        (
+            None,
+            TagEncoding::Niche {
+                untagged_variant, ..
+            },
+        ) => Some(untagged_variant.to_index()),
+        (None, TagEncoding::Direct) => None,
+    }
+}
+
+/// Read an initialized unsigned integer of `size` bytes.
+fn read_uint(alloc: &Allocation, offset: usize, size: usize, endian: Endian) -> Option<u128> {
+    let bytes = bytes(alloc, offset, size)?
+        .iter()
+        .copied()
+        .collect::<Option<Vec<u8>>>()?;
+    let fold = |value: u128, byte: &u8| value << 8 | u128::from(*byte);
+    Some(match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    })
+}
+
+fn bytes(alloc: &Allocation, offset: usize, size: usize) -> Option<&[Option<u8>]> {
+    alloc.bytes.get(offset..offset.checked_add(size)?)
+}
+
+/// The allocation a pointer stored at `offset` points into.
+fn provenance(alloc: &Allocation, offset: usize) -> Option<AllocId> {
+    alloc
+        .provenance
+        .ptrs
+        .iter()
+        .find(|(ptr_offset, _)| *ptr_offset == offset)
+        .map(|(_, prov)| prov.0)
+}
+
+fn layout_of(metadata: &TypeMetadata) -> Option<&LayoutShape> {
+    match metadata {
+        TypeMetadata::EnumType { layout, .. }
+        | TypeMetadata::StructType { layout, .. }
+        | TypeMetadata::UnionType { layout, .. }
+        | TypeMetadata::ArrayType { layout, .. }
+        | TypeMetadata::PtrType { layout, .. }
+        | TypeMetadata::RefType { layout, .. }
+        | TypeMetadata::TupleType { layout, .. }
+        | TypeMetadata::DynType { layout, .. }
+        | TypeMetadata::FnSigType { layout, .. }
+        | TypeMetadata::ClosureType { layout, .. }
+        | TypeMetadata::CoroutineType { layout, .. }
+        | TypeMetadata::PatternType { layout, .. }
+        | TypeMetadata::ForeignType { layout, .. } => layout.as_ref(),
+        _ => None,
+    }
+}
+
+fn primitive_size(rigid: &RigidTy) -> Option<usize> {
+    match rigid {
+        RigidTy::Bool => Some(1),
+        RigidTy::Char => Some(4),
+        RigidTy::Int(int) => Some(int.num_bytes()),
+        RigidTy::Uint(uint) => Some(uint.num_bytes()),
+        RigidTy::Float(FloatTy::F16) => Some(2),
+        RigidTy::Float(FloatTy::F32) => Some(4),
+        RigidTy::Float(FloatTy::F64) => Some(8),
+        RigidTy::Float(FloatTy::F128) => Some(16),
+        RigidTy::Never => Some(0),
+        _ => None,
+    }
+}
@@ -0,0 +1,102 @@
+//! Restricting the output to part of the crate.
+//!
+//! By default every mono item from `mono_collect` is analyzed. An
+//! [`ItemFilter`] narrows this down in two ways, which can be combined:
+//!
+//! - **Path globs** (`--smir-filter-path`): only items whose def path (e.g.
+//!   `verify::check` or `main` in the local crate, `std::rt::lang_start` in
+//!   a dependency) matches one of the globs are kept. Local paths carry no
+//!   crate name. `*` matches any sequence of characters, including `::`, and
+//!   `?` matches a single character.
+//! - **Entry points** (`--smir-reachable-from`): only items reachable from an
+//!   item with one of the given def paths are kept. Reachability follows the
+//!   callees that body analysis discovers (calls, drops, function pointers,
+//!   and the methods of vtables a body creates) and statics a body refers to.
+//!   With path globs as well, the traversal does not leave the matching items.
+//!
+//! Unevaluated constants used by a kept body are always kept. Since only kept
+//! bodies are analyzed, `functions`, `types`, `allocs` and `spans` contain
+//! only what those bodies reference. Generic items (`--smir-polymorphic`) are
+//! not mono items and are only subject to the path globs.
+
+use crate::compat::serde;
+use crate::compat::stable_mir;
+
+use serde::Serialize;
+use stable_mir::mir::mono::MonoItem;
+use stable_mir::CrateDef;
+
+/// Which mono items to keep; see the [module documentation](self).
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemFilter {
+    /// Def path globs; an item is kept if its path matches any of them.
+    pub paths: Vec<String>,
+    /// Def paths of the entry points of the reachability filter.
+    pub reachable_from: Vec<String>,
+}
+
+impl ItemFilter {
+    /// Whether `item` passes the path globs (always, if there are none).
+    pub(super) fn matches(&self, item: &MonoItem) -> bool {
+        if self.paths.is_empty() {
+            return true;
+        }
+        mono_item_path(item).is_some_and(|path| self.matches_path(&path))
+    }
+
+    /// Whether the def path `path` passes the path globs (always, if there
+    /// are none).
+    pub(super) fn matches_path(&self, path: &str) -> bool {
+        self.paths.is_empty() || self.paths.iter().any(|g| glob_match(g, path))
+    }
+
+    /// The entry point of the reachability filter that `item` is an instance
+    /// of, if any.
+    pub(super) fn entry_point(&self, item: &MonoItem) -> Option<&str> {
+        let path = mono_item_path(item)?;
+        self.reachable_from
+            .iter()
+            .find(|entry| **entry == path)
+            .map(String::as_str)
+    }
+}
+
+/// Def path of the definition behind a mono item, without generic arguments.
+fn mono_item_path(item: &MonoItem) -> Option<String> {
+    match item {
+        MonoItem::Fn(inst) => Some(inst.def.name()),
+        MonoItem::Static(def) => Some(def.name()),
+        MonoItem::GlobalAsm(_) => None,
+    }
+}
+
+/// Match `text` against a glob where `*` matches any sequence of characters
+/// and `?` any single character.
+fn glob_match(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position of the last `*` and the text position it currently absorbs up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, t));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    g = star + 1;
+                    t = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
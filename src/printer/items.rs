@@ -6,6 +6,11 @@
 //! split is what keeps `MonoItem` out of [`Item`] while still making it
 //! available during collection.
 //!
+//! [`mk_upstream_item`] builds the item for a function of an upstream crate
+//! (see [`upstream`](super::upstream)), and [`mk_generic_item`] builds the
+//! [`GenericItem`] for a generic function of the local crate, which has no
+//! `MonoItem`.
+//!
 //! Also handles optional debug-level details (instance kind, body pretty-print,
 //! generic parameters, internal type info) and foreign module enumeration.
 
@@ -20,16 +25,24 @@ use stable_mir::mir::Body;
 use stable_mir::ty::Allocation;
 use stable_mir::{CrateDef, CrateItem};
 
-use crate::compat::bridge::mono_instance;
+use crate::compat::bridge::{instance_kind, mono_instance, InstanceKindTag};
 
-use super::schema::{BodyDetails, ForeignItem, ForeignModule, GenericData, Item, ItemDetails};
+use super::schema::{
+    BodyDetails, FnAbiInfo, ForeignItem, ForeignModule, GenericData, GenericItem, Item, ItemDetails,
+};
 
 #[derive(Serialize, Clone)]
 pub enum MonoItemKind {
     MonoItemFn {
         name: String,
         id: stable_mir::DefId,
+        /// Whether this is the function's own body or a shim (drop glue,
+        /// clone shim, ...) generated for it.
+        instance_kind: InstanceKindTag,
         body: Option<Body>,
+        /// How the function is called: argument passing modes and calling
+        /// convention.
+        fn_abi: Option<Box<FnAbiInfo>>,
     },
     MonoItemStatic {
         name: String,
@@ -54,8 +67,9 @@ fn get_item_details(
     id: DefId,
     fn_inst: Option<Instance>,
     fn_body: Option<&Body>,
+    debug: bool,
 ) -> Option<ItemDetails> {
-    if super::debug_enabled() {
+    if debug {
         let (internal_kind, path, internal_ty) = crate::compat::types::get_def_info(tcx, id);
         Some(ItemDetails {
             fn_instance_kind: fn_inst.map(|i| i.kind),
@@ -73,14 +87,19 @@ fn get_item_details(
     }
 }
 
-pub(super) fn mk_item(tcx: TyCtxt<'_>, item: MonoItem, sym_name: String) -> (MonoItem, Item) {
+pub(super) fn mk_item(
+    tcx: TyCtxt<'_>,
+    item: MonoItem,
+    sym_name: String,
+    debug: bool,
+) -> (MonoItem, Item) {
     match item {
         MonoItem::Fn(inst) => {
             let id = inst.def.def_id();
             let name = inst.name();
             let internal_id = crate::compat::types::internal_def_id(tcx, id);
             let body = inst.body();
-            let details = get_item_details(tcx, internal_id, Some(inst), body.as_ref());
+            let details = get_item_details(tcx, internal_id, Some(inst), body.as_ref(), debug);
             let mono_item = MonoItem::Fn(inst);
             (
                 mono_item,
@@ -89,7 +108,9 @@ pub(super) fn mk_item(tcx: TyCtxt<'_>, item: MonoItem, sym_name: String) -> (Mon
                     MonoItemKind::MonoItemFn {
                         name: name.clone(),
                         id,
+                        instance_kind: instance_kind(tcx, &inst).tag(),
                         body,
+                        fn_abi: inst.fn_abi().ok().map(|abi| Box::new(abi.into())),
                     },
                     details,
                 ),
@@ -119,7 +140,7 @@ pub(super) fn mk_item(tcx: TyCtxt<'_>, item: MonoItem, sym_name: String) -> (Mon
                         allocation: alloc,
                         body,
                     },
-                    get_item_details(tcx, internal_id, None, None),
+                    get_item_details(tcx, internal_id, None, None, debug),
                 ),
             )
         }
@@ -137,6 +158,39 @@ pub(super) fn mk_item(tcx: TyCtxt<'_>, item: MonoItem, sym_name: String) -> (Mon
     }
 }
 
+/// Build the item for a function of the upstream crate `krate`, or `None`
+/// if that crate encoded no MIR for it.
+pub(super) fn mk_upstream_item(
+    tcx: TyCtxt<'_>,
+    inst: Instance,
+    sym_name: String,
+    krate: String,
+    debug: bool,
+) -> Option<(MonoItem, Item)> {
+    let (mono_item, mut item) = mk_item(tcx, MonoItem::Fn(inst), sym_name, debug);
+    if let MonoItemKind::MonoItemFn {
+        body: body @ None, ..
+    } = &mut item.mono_item_kind
+    {
+        *body = Some(crate::compat::types::ctfe_body(tcx, &inst)?);
+    }
+    item.external_crate = Some(krate);
+    Some((mono_item, item))
+}
+
+/// Build the [`GenericItem`] for a generic function that has a body.
+pub(super) fn mk_generic_item(tcx: TyCtxt<'_>, item: CrateItem) -> GenericItem {
+    let id = item.def_id();
+    let (generics, predicates) = crate::compat::types::generics_and_predicates(tcx, id);
+    GenericItem {
+        name: item.name(),
+        id,
+        generics,
+        predicates,
+        body: item.body(),
+    }
+}
+
 pub(super) fn get_foreign_module_details() -> Vec<(String, Vec<ForeignModule>)> {
     let mut crates = vec![stable_mir::local_crate()];
     crates.append(&mut stable_mir::external_crates());
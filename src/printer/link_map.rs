@@ -6,16 +6,23 @@
 //! - `TERM`: the function is called in a `Call` or `Drop` terminator,
 //! - `FPTR`: the function is referenced via a `ReifyFnPointer` cast or a
 //!   zero-sized FnDef constant.
+//!
+//! With `LINK_INST`, a second symbol for the same key is not added to the
+//! map but recorded as a [`LinkMapConflict`], which is reported as a
+//! compiler warning and listed in the debug output.
 
 use crate::compat::middle::ty::TyCtxt;
 use crate::compat::stable_mir;
 
-use crate::compat::bridge::OpaqueInstanceKind;
+use crate::compat::bridge::{InstanceKindRepr, InstanceKindTag};
+use crate::compat::diagnostics::warn_with_notes;
 use stable_mir::mir::mono::Instance;
 
-use super::schema::{FnSymType, ItemSource, LinkMap, LinkMapKey};
+use super::schema::{
+    FnSymType, ItemSource, LinkMap, LinkMapCandidate, LinkMapConflict, LinkMapKey,
+};
 
-pub(super) type FnSymInfo = (stable_mir::ty::Ty, OpaqueInstanceKind, FnSymType);
+pub(super) type FnSymInfo = (stable_mir::ty::Ty, InstanceKindRepr, FnSymType);
 
 pub(super) fn fn_inst_sym(
     tcx: TyCtxt<'_>,
@@ -44,22 +51,24 @@ pub(super) fn fn_inst_sym(
 
 pub(super) fn update_link_map(
     link_map: &mut LinkMap,
+    conflicts: &mut Vec<LinkMapConflict>,
     fn_sym: Option<FnSymInfo>,
     source: ItemSource,
+    link_inst: bool,
 ) {
     let Some((ty, kind, name)) = fn_sym else {
         return;
     };
-    let is_reify_shim = kind.is_reify_shim;
-    let new_val = (source, name.clone());
-    let key = if super::link_instance_enabled() {
+    let is_reify_shim = kind.tag() == InstanceKindTag::ReifyShim;
+    let new_val = (source, name.clone(), kind.tag());
+    let key = if link_inst {
         LinkMapKey(ty, Some(kind))
     } else {
         LinkMapKey(ty, None)
     };
     if let Some(curr_val) = link_map.get_mut(&key) {
         if curr_val.1 != new_val.1 {
-            if !super::link_instance_enabled() {
+            if !link_inst {
                 // When LINK_INST is disabled, prefer Item over ReifyShim.
                 // ReifyShim has no body in items, so Item is more useful.
                 if is_reify_shim {
@@ -68,15 +77,36 @@ pub(super) fn update_link_map(
                 }
                 // New entry is Item, existing is ReifyShim -> replace
                 curr_val.1 = name;
+                curr_val.2 = new_val.2;
                 curr_val.0 .0 |= new_val.0 .0;
                 return;
             }
-            panic!(
-                "Added inconsistent entries into link map! {:?} -> {:?}, {:?}",
-                (ty, ty.kind().fn_def(), &key.1),
+            debug_log_println!(
+                "Inconsistent link map entry: {:?}:{:?} -> {:?}, {:?}",
+                &key,
+                ty.kind().fn_def(),
                 curr_val.1,
                 new_val.1
             );
+            let (sources, name, _) = new_val;
+            match conflicts
+                .iter_mut()
+                .find(|c| c.key == key && c.rejected.symbol == name)
+            {
+                Some(conflict) => conflict.rejected.sources.0 |= sources.0,
+                None => conflicts.push(LinkMapConflict {
+                    key,
+                    kept: LinkMapCandidate {
+                        symbol: curr_val.1.clone(),
+                        sources: curr_val.0.clone(),
+                    },
+                    rejected: LinkMapCandidate {
+                        symbol: name,
+                        sources,
+                    },
+                }),
+            }
+            return;
         }
         curr_val.0 .0 |= new_val.0 .0;
         debug_log_println!(
@@ -95,3 +125,45 @@ pub(super) fn update_link_map(
         link_map.insert(key, new_val);
     }
 }
+
+/// Report the link map conflicts as compiler warnings, in a deterministic
+/// order and with the sources of the kept symbols brought up to date.
+pub(super) fn report_link_map_conflicts(
+    tcx: TyCtxt<'_>,
+    link_map: &LinkMap,
+    conflicts: &mut [LinkMapConflict],
+) {
+    conflicts.sort_by(|a, b| {
+        format!("{}", a.key.0)
+            .cmp(&format!("{}", b.key.0))
+            .then_with(|| a.kept.symbol.cmp(&b.kept.symbol))
+            .then_with(|| a.rejected.symbol.cmp(&b.rejected.symbol))
+    });
+    for conflict in conflicts.iter_mut() {
+        if let Some((sources, ..)) = link_map.get(&conflict.key) {
+            conflict.kept.sources = sources.clone();
+        }
+        let kind = match &conflict.key.1 {
+            Some(kind) => format!(" ({:?})", kind.tag()),
+            None => String::new(),
+        };
+        let notes = [("kept", &conflict.kept), ("rejected", &conflict.rejected)]
+            .into_iter()
+            .map(|(label, candidate)| {
+                format!(
+                    "{label} {:?}, found in: {}",
+                    candidate.symbol,
+                    candidate.sources.names().join(", ")
+                )
+            })
+            .collect();
+        warn_with_notes(
+            tcx,
+            format!(
+                "inconsistent link map entries for `{}`{kind}",
+                conflict.key.0
+            ),
+            notes,
+        );
+    }
+}
@@ -2,31 +2,43 @@
 //!
 //! [`BodyAnalyzer`] implements `MirVisitor` and walks each function body exactly
 //! once, collecting:
-//! - function calls and drop glue into the link map
+//! - function calls and drop glue into the link map, and as edges of the
+//!   call graph
 //! - global allocations (memory, statics, vtables, function pointers) with
 //!   provenance type resolution via [`get_prov_ty`]
+//! - the vtables of unsizing casts and vtable allocations, whose functions
+//!   are added to the link map
 //! - reachable types via the type visitor
 //! - source spans
 //!
 //! [`get_prov_ty`] recursively resolves the type of a pointer at a given byte
-//! offset within a struct or tuple, walking down through nested fields until it
-//! reaches the actual pointer type.
+//! offset within a struct, tuple, array or enum, walking down through nested
+//! fields until it reaches the actual pointer type. For enums, the active
+//! variant is read from the tag in the allocation's bytes.
 
+use crate::compat::bridge::InstanceKindTag;
 use crate::compat::middle::ty::TyCtxt;
 use crate::compat::stable_mir;
+use crate::compat::types::{vtable_slots, VTableSlot};
 
-use stable_mir::abi::{FieldsShape, LayoutShape};
+use stable_mir::abi::{FieldsShape, VariantsShape};
 use stable_mir::mir::alloc::GlobalAlloc;
-use stable_mir::mir::mono::Instance;
+use stable_mir::mir::mono::{Instance, InstanceKind, MonoItem};
 use stable_mir::mir::visit::MirVisitor;
 use stable_mir::mir::{LocalDecl, Rvalue, Terminator, TerminatorKind};
-use stable_mir::ty::{ConstDef, IndexedVal};
+use stable_mir::target::MachineInfo;
+use stable_mir::ty::{Allocation, Binder, ConstDef, ExistentialTraitRef, IndexedVal, Span};
 use stable_mir::visitor::Visitable;
 use stable_mir::CrateDef;
 
-use super::link_map::{fn_inst_sym, update_link_map};
-use super::schema::{AllocMap, ItemSource, LinkMap, SpanMap, FPTR, ITEM, TERM};
+use super::decode::read_variant;
+use super::link_map::{fn_inst_sym, update_link_map, FnSymInfo};
+use super::schema::{
+    AllocMap, CallEdge, CallKind, FnSymType, ItemSource, LinkMap, LinkMapConflict, LinkMapKey,
+    OutputFlags, SpanMap, VTableEntry, VTableFn, VTableInfo, VTableMap, FPTR, ITEM, TERM, VTBL,
+};
 use super::ty_visitor::TyCollector;
+use super::types::mk_enum_layout;
 use super::util::fn_inst_for_ty;
 
 /// Single-pass body visitor that collects all derived information from a MIR body:
@@ -39,12 +51,178 @@ pub(super) struct BodyAnalyzer<'tcx, 'local> {
     pub tcx: TyCtxt<'tcx>,
     pub locals: &'local [LocalDecl],
     pub link_map: &'local mut LinkMap,
+    /// Symbols that conflicted with an entry of `link_map`.
+    pub link_conflicts: &'local mut Vec<LinkMapConflict>,
     pub visited_allocs: &'local mut AllocMap,
     pub ty_visitor: &'local mut TyCollector<'tcx>,
     pub spans: &'local mut SpanMap,
+    pub vtables: &'local mut VTableMap,
+    /// Symbol name of the item whose body is walked, the caller of the
+    /// edges added to `call_edges`.
+    pub caller: &'local str,
+    pub call_edges: &'local mut Vec<CallEdge>,
+    /// Key the link map by instance kind as well as type (`LINK_INST`).
+    pub link_inst: bool,
+    /// The body is a generic item's polymorphic body. Callees, function
+    /// pointers and unevaluated constants in it cannot be resolved to
+    /// instances, so they are not recorded.
+    pub polymorphic: bool,
+    /// Shims (drop glue, clone shims, ...) that this body calls or drops
+    /// with, whose bodies the outer fixpoint loop adds to the output if no
+    /// mono item of the crate provides them.
+    pub called_shims: &'local mut Vec<Instance>,
     /// Unevaluated constants discovered during this body walk.
     /// The outer fixpoint loop uses these to discover and create new Items.
     pub new_unevaluated: &'local mut Vec<UnevalConstInfo>,
+    /// Mono items this body calls or refers to, recorded only when a
+    /// reachability filter is active. Allocations already visited from
+    /// another body are not reported again.
+    pub reached: Option<&'local mut Vec<MonoItem>>,
+}
+
+impl BodyAnalyzer<'_, '_> {
+    /// Record a called instance for [`Self::called_shims`] if it is a shim
+    /// with a body.
+    fn call_shim(&mut self, inst: Instance) {
+        if inst.kind == InstanceKind::Shim && !inst.is_empty_shim() {
+            self.called_shims.push(inst);
+        }
+    }
+
+    fn reach(&mut self, item: MonoItem) {
+        if let Some(reached) = &mut self.reached {
+            reached.push(item);
+        }
+    }
+
+    /// Add an edge from the body's item to the function of `fn_sym`.
+    fn add_call_edge(&mut self, fn_sym: Option<&FnSymInfo>, kind: CallKind, span: Span) {
+        if let Some((_, _, callee)) = fn_sym {
+            self.call_edges.push(CallEdge {
+                caller: self.caller.to_string(),
+                callee: callee.clone(),
+                kind,
+                span,
+            });
+        }
+    }
+
+    /// Record the function and static allocations reachable from `alloc`.
+    /// The methods of a vtable are recorded by [`Self::collect_vtable`].
+    fn reach_alloc(&mut self, alloc: &GlobalAlloc) {
+        match alloc {
+            GlobalAlloc::Function(inst) => self.reach(MonoItem::Fn(*inst)),
+            GlobalAlloc::Static(def) => self.reach(MonoItem::Static(*def)),
+            GlobalAlloc::VTable(..) | GlobalAlloc::Memory(_) => {}
+        }
+    }
+
+    /// Record the vtable that an unsizing cast from `source` to a `dyn
+    /// Trait` pointer `target` needs, and its drop glue.
+    fn visit_unsize(&mut self, source: stable_mir::ty::Ty, target: stable_mir::ty::Ty) {
+        let pointee = |ty: stable_mir::ty::Ty| ty.kind().builtin_deref(true).map(|t| t.ty);
+        let (Some(source), Some(target)) = (pointee(source), pointee(target)) else {
+            return;
+        };
+        let target = target.kind();
+        if !target.is_trait() || source.kind().is_trait() {
+            return;
+        }
+        // Like rustc's mono item collector, count the drop glue even when the
+        // vtable's drop slot is empty.
+        self.reach(MonoItem::Fn(Instance::resolve_drop_in_place(source)));
+        self.collect_vtable(source, target.trait_principal());
+    }
+
+    /// Record the vtable of `ty` as a `dyn` of `trait_ref` (and the vtables
+    /// of its supertraits), adding the functions in its slots to the link
+    /// map.
+    fn collect_vtable(
+        &mut self,
+        ty: stable_mir::ty::Ty,
+        trait_ref: Option<Binder<ExistentialTraitRef>>,
+    ) {
+        let vtable = GlobalAlloc::VTable(ty, trait_ref.clone());
+        let Some(alloc_id) = vtable.vtable_allocation() else {
+            return;
+        };
+        if self.vtables.contains_key(&alloc_id) {
+            return;
+        }
+        let Ok(layout) = ty.layout().map(|l| l.shape()) else {
+            return;
+        };
+        ty.visit(self.ty_visitor);
+        let mut supertraits = Vec::new();
+        let entries = vtable_slots(self.tcx, ty, trait_ref.as_ref())
+            .into_iter()
+            .map(|slot| match slot {
+                VTableSlot::DropInPlace(inst) => {
+                    VTableEntry::MetadataDropInPlace(inst.map(|inst| self.vtable_fn(inst)))
+                }
+                VTableSlot::Size => VTableEntry::MetadataSize,
+                VTableSlot::Align => VTableEntry::MetadataAlign,
+                VTableSlot::Vacant => VTableEntry::Vacant,
+                VTableSlot::Method(inst) => VTableEntry::Method(self.vtable_fn(inst)),
+                VTableSlot::TraitVPtr(supertrait) => {
+                    supertraits.push(supertrait.clone());
+                    VTableEntry::TraitVPtr(supertrait)
+                }
+            })
+            .collect();
+        let trait_name = trait_ref
+            .as_ref()
+            .map(|trait_ref| trait_ref.value.def_id.name());
+        self.vtables.insert(
+            alloc_id,
+            VTableInfo {
+                ty,
+                trait_ref,
+                trait_name,
+                size: layout.size.bytes() as u64,
+                align: layout.abi_align,
+                entries,
+            },
+        );
+        for supertrait in supertraits {
+            self.collect_vtable(ty, Some(supertrait));
+        }
+    }
+
+    /// The function in a vtable slot, which is reached and added to the link
+    /// map. It has no key where the key would name another instance: a
+    /// closure body has the closure's type rather than a function definition
+    /// type, and without `LINK_INST` a reify or vtable shim shares its key
+    /// with the function it wraps.
+    fn vtable_fn(&mut self, inst: Instance) -> VTableFn {
+        self.reach(MonoItem::Fn(inst));
+        let Some((ty, kind, symbol)) = fn_inst_sym(self.tcx, None, Some(&inst)) else {
+            return VTableFn {
+                key: None,
+                symbol: FnSymType::NormalSym(inst.mangled_name()),
+            };
+        };
+        if !self.link_inst
+            && matches!(
+                kind.tag(),
+                InstanceKindTag::ReifyShim | InstanceKindTag::VTableShim
+            )
+        {
+            return VTableFn { key: None, symbol };
+        }
+        let key = LinkMapKey(ty, self.link_inst.then(|| kind.clone()));
+        update_link_map(
+            self.link_map,
+            self.link_conflicts,
+            Some((ty, kind, symbol.clone())),
+            ItemSource(VTBL),
+            self.link_inst,
+        );
+        VTableFn {
+            key: Some(key),
+            symbol,
+        }
+    }
 }
 
 /// Information about an unevaluated constant discovered during body analysis.
@@ -61,39 +239,29 @@ pub(super) fn maybe_add_to_link_map(
     tcx: TyCtxt<'_>,
     mono_item: &stable_mir::mir::mono::MonoItem,
     link_map: &mut LinkMap,
+    link_conflicts: &mut Vec<LinkMapConflict>,
+    flags: &OutputFlags,
 ) {
-    if !super::link_items_enabled() {
+    if !flags.link_items {
         return;
     }
     if let stable_mir::mir::mono::MonoItem::Fn(inst) = mono_item {
         update_link_map(
             link_map,
+            link_conflicts,
             fn_inst_sym(tcx, None, Some(inst)),
             ItemSource(ITEM),
+            flags.link_inst,
         );
     }
 }
 
-/// Returns the field index (source order) for a given offset and layout if
-/// the layout contains fields (shared between all variants), otherwise None.
-/// NB No search for fields within variants (needs recursive call).
-fn field_for_offset(l: &LayoutShape, offset: usize) -> Option<usize> {
-    match &l.fields {
-        FieldsShape::Primitive | FieldsShape::Union(_) | FieldsShape::Array { .. } => None,
-        FieldsShape::Arbitrary { offsets } => offsets
-            .iter()
-            .enumerate()
-            .find(|(_, o)| o.bytes() == offset)
-            .map(|(i, _)| i),
-    }
-}
-
 /// Find the field whose byte range contains the given offset by scanning for
 /// the field with the largest start offset that doesn't exceed the target.
 /// Returns `(field_index, field_start_byte_offset)`. Single linear pass; no
 /// allocation or sorting needed since we only track the running best.
-fn field_containing_offset(l: &LayoutShape, offset: usize) -> Option<(usize, usize)> {
-    match &l.fields {
+fn field_containing_offset(fields: &FieldsShape, offset: usize) -> Option<(usize, usize)> {
+    match fields {
         FieldsShape::Arbitrary { offsets } => {
             let mut best: Option<(usize, usize)> = None;
             for (i, o) in offsets.iter().enumerate() {
@@ -114,11 +282,15 @@ fn field_containing_offset(l: &LayoutShape, offset: usize) -> Option<(usize, usi
     }
 }
 
-fn opaque_placeholder_ty() -> stable_mir::ty::Ty {
-    stable_mir::ty::Ty::to_val(0)
-}
-
-fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty::Ty> {
+/// The pointee type of the pointer at `offset` within a value of type `ty`,
+/// which is stored at `start` in `alloc` (needed to tell enum variants apart).
+fn get_prov_ty(
+    tcx: TyCtxt<'_>,
+    alloc: &Allocation,
+    ty: stable_mir::ty::Ty,
+    start: usize,
+    offset: &usize,
+) -> Option<stable_mir::ty::Ty> {
     use stable_mir::ty::RigidTy;
     let ty_kind = ty.kind();
     debug_log_println!("get_prov_ty: {:?} offset={}", ty_kind, offset);
@@ -154,9 +326,22 @@ fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty:
             return None;
         }
     };
-    let ref_ty = match rigid {
-        // homogenous, so no choice. Could check alignment of the offset...
-        RigidTy::Array(ty, _) | RigidTy::Slice(ty) => Some(*ty),
+    match rigid {
+        // homogenous, so no choice: recurse into the element containing the offset
+        RigidTy::Array(elem_ty, _) | RigidTy::Slice(elem_ty) => {
+            let stride = elem_ty.layout().ok()?.shape().size.bytes();
+            if stride == 0 {
+                return None;
+            }
+            let elem_start = *offset - *offset % stride;
+            get_prov_ty(
+                tcx,
+                alloc,
+                *elem_ty,
+                start + elem_start,
+                &(*offset % stride),
+            )
+        }
         // cases covered above
         RigidTy::Ref(_, _, _) | RigidTy::RawPtr(_, _) => {
             unreachable!("Covered by builtin_deref above")
@@ -168,7 +353,7 @@ fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty:
         // The provenance offset may point into a nested struct field, so we
         // walk down through the field hierarchy until we reach the pointer.
         RigidTy::Adt(adt_def, args) if ty_kind.is_struct() => {
-            let (field_idx, field_start) = field_containing_offset(&layout, *offset)?;
+            let (field_idx, field_start) = field_containing_offset(&layout.fields, *offset)?;
             // NB struct, single variant
             let fields = adt_def.variants().pop().map(|v| v.fields())?;
             let field_ty = fields.get(field_idx)?.ty_with_args(args);
@@ -177,20 +362,32 @@ fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty:
                 "get_prov_ty: struct {:?} offset={} -> field {} (start={}) type {:?}, relative_offset={}",
                 adt_def, offset, field_idx, field_start, field_ty.kind(), relative_offset
             );
-            return get_prov_ty(field_ty, &relative_offset);
+            get_prov_ty(tcx, alloc, field_ty, start + field_start, &relative_offset)
         }
-        RigidTy::Adt(_adt_def, _args) if ty_kind.is_enum() => {
-            // we have to figure out which variant we are dealing with (requires the data)
-            match field_for_offset(&layout, *offset) {
-                // FIXME we'd have to figure out which variant we are dealing with (requires the data)
-                None => None,
-                // FIXME we'd have to figure out where that shared field is in the source ordering
-                Some(_idx) => None,
-            }
+        // For enums, read the tag from the data to find the active variant,
+        // then proceed as for structs with that variant's fields.
+        RigidTy::Adt(adt_def, args) if ty_kind.is_enum() => {
+            let discriminants = crate::compat::types::adt_discriminants(tcx, *adt_def);
+            let enum_layout = mk_enum_layout(&layout, &discriminants);
+            let variant_idx =
+                read_variant(alloc, start, &enum_layout, MachineInfo::target().endian)?;
+            let variant_fields = match &layout.variants {
+                VariantsShape::Single { .. } => &layout.fields,
+                VariantsShape::Multiple { variants, .. } => &variants.get(variant_idx)?.fields,
+            };
+            let (field_idx, field_start) = field_containing_offset(variant_fields, *offset)?;
+            let variant = adt_def.variants().into_iter().nth(variant_idx)?;
+            let field_ty = variant.fields().get(field_idx)?.ty_with_args(args);
+            let relative_offset = *offset - field_start;
+            debug_log_println!(
+                "get_prov_ty: enum {:?} offset={} -> variant {} field {} (start={}) type {:?}, relative_offset={}",
+                adt_def, offset, variant_idx, field_idx, field_start, field_ty.kind(), relative_offset
+            );
+            get_prov_ty(tcx, alloc, field_ty, start + field_start, &relative_offset)
         }
         // Same as structs: find containing field and recurse.
         RigidTy::Tuple(fields) => {
-            let (field_idx, field_start) = field_containing_offset(&layout, *offset)?;
+            let (field_idx, field_start) = field_containing_offset(&layout.fields, *offset)?;
             let field_ty = *fields.get(field_idx)?;
             let relative_offset = *offset - field_start;
             debug_log_println!(
@@ -201,7 +398,7 @@ fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty:
                 field_ty.kind(),
                 relative_offset
             );
-            return get_prov_ty(field_ty, &relative_offset);
+            get_prov_ty(tcx, alloc, field_ty, start + field_start, &relative_offset)
         }
         RigidTy::FnPtr(_) => None,
         _unimplemented => {
@@ -211,15 +408,12 @@ fn get_prov_ty(ty: stable_mir::ty::Ty, offset: &usize) -> Option<stable_mir::ty:
             );
             None
         }
-    };
-    match ref_ty {
-        None => None,
-        Some(ty) => get_prov_ty(ty, &0),
     }
 }
 
 fn collect_alloc(
     val_collector: &mut BodyAnalyzer,
+    parent: &Allocation,
     ty: stable_mir::ty::Ty,
     offset: usize,
     val: stable_mir::mir::alloc::AllocId,
@@ -229,6 +423,10 @@ fn collect_alloc(
     }
     let kind = ty.kind();
     let global_alloc = GlobalAlloc::from(val);
+    val_collector.reach_alloc(&global_alloc);
+    if let GlobalAlloc::VTable(vtable_ty, trait_ref) = &global_alloc {
+        val_collector.collect_vtable(*vtable_ty, trait_ref.clone());
+    }
     debug_log_println!(
         "DEBUG: called collect_alloc: {:?}:{:?}:{:?}",
         val,
@@ -237,7 +435,7 @@ fn collect_alloc(
     );
     match global_alloc {
         GlobalAlloc::Memory(ref alloc) => {
-            let pointed_ty = get_prov_ty(ty, &offset);
+            let pointed_ty = get_prov_ty(val_collector.tcx, parent, ty, 0, &offset);
             debug_log_println!(
                 "DEBUG: adding alloc: {:?}:{:?}: {:?}",
                 val,
@@ -247,18 +445,18 @@ fn collect_alloc(
             if let Some(p_ty) = pointed_ty {
                 val_collector
                     .visited_allocs
-                    .insert(val, (p_ty, global_alloc.clone()));
+                    .insert(val, (Some(p_ty), global_alloc.clone()));
                 alloc
                     .provenance
                     .ptrs
                     .iter()
                     .for_each(|(prov_offset, prov)| {
-                        collect_alloc(val_collector, p_ty, *prov_offset, prov.0);
+                        collect_alloc(val_collector, alloc, p_ty, *prov_offset, prov.0);
                     });
             } else {
                 val_collector
                     .visited_allocs
-                    .insert(val, (opaque_placeholder_ty(), global_alloc.clone()));
+                    .insert(val, (None, global_alloc.clone()));
             }
         }
         GlobalAlloc::Static(_) | GlobalAlloc::VTable(_, _) | GlobalAlloc::Function(_) => {
@@ -272,7 +470,7 @@ fn collect_alloc(
             };
 
             if needs_recovery {
-                let prov_ty = get_prov_ty(ty, &offset);
+                let prov_ty = get_prov_ty(val_collector.tcx, parent, ty, 0, &offset);
                 debug_log_println!(
                     "DEBUG: {:?} with non-direct type; alloc_id={:?}, ty={:?}, offset={}, kind={:?}, recovered_prov_ty={:?}",
                     global_alloc,
@@ -285,18 +483,18 @@ fn collect_alloc(
                 if let Some(p_ty) = prov_ty {
                     val_collector
                         .visited_allocs
-                        .insert(val, (p_ty, global_alloc.clone()));
+                        .insert(val, (Some(p_ty), global_alloc.clone()));
                 } else {
                     // Recovery failed: do not treat outer container `ty` as pointee.
                     val_collector
                         .visited_allocs
-                        .insert(val, (opaque_placeholder_ty(), global_alloc.clone()));
+                        .insert(val, (None, global_alloc.clone()));
                 }
             } else {
                 // Type is already the correct pointee; use it directly.
                 val_collector
                     .visited_allocs
-                    .insert(val, (ty, global_alloc.clone()));
+                    .insert(val, (Some(ty), global_alloc.clone()));
             }
         }
     };
@@ -313,6 +511,9 @@ impl MirVisitor for BodyAnalyzer<'_, '_> {
     fn visit_terminator(&mut self, term: &Terminator, loc: stable_mir::mir::visit::Location) {
         use stable_mir::mir::{ConstOperand, Operand::Constant};
         use TerminatorKind::*;
+        if self.polymorphic {
+            return self.super_terminator(term, loc);
+        }
         let fn_sym = match &term.kind {
             Call {
                 func: Constant(ConstOperand { const_: cnst, .. }),
@@ -324,30 +525,61 @@ impl MirVisitor for BodyAnalyzer<'_, '_> {
                 } else {
                     let inst = fn_inst_for_ty(cnst.ty(), true)
                         .expect("Direct calls to functions must resolve to an instance");
-                    fn_inst_sym(self.tcx, Some(cnst.ty()), Some(&inst))
+                    let fn_sym = fn_inst_sym(self.tcx, Some(cnst.ty()), Some(&inst));
+                    let kind = match inst.kind {
+                        InstanceKind::Virtual { idx } => CallKind::Virtual { idx },
+                        _ => CallKind::Direct,
+                    };
+                    self.add_call_edge(fn_sym.as_ref(), kind, term.span);
+                    self.call_shim(inst);
+                    self.reach(MonoItem::Fn(inst));
+                    fn_sym
                 }
             }
             Drop { place, .. } => {
                 let drop_ty = place.ty(self.locals).unwrap();
                 let inst = Instance::resolve_drop_in_place(drop_ty);
-                fn_inst_sym(self.tcx, None, Some(&inst))
+                let fn_sym = fn_inst_sym(self.tcx, None, Some(&inst));
+                self.add_call_edge(fn_sym.as_ref(), CallKind::DropGlue, term.span);
+                self.call_shim(inst);
+                self.reach(MonoItem::Fn(inst));
+                fn_sym
             }
             _ => None,
         };
-        update_link_map(self.link_map, fn_sym, ItemSource(TERM));
+        update_link_map(
+            self.link_map,
+            self.link_conflicts,
+            fn_sym,
+            ItemSource(TERM),
+            self.link_inst,
+        );
         self.super_terminator(term, loc);
     }
 
     fn visit_rvalue(&mut self, rval: &Rvalue, loc: stable_mir::mir::visit::Location) {
         use stable_mir::mir::{CastKind, PointerCoercion};
+        if self.polymorphic {
+            return self.super_rvalue(rval, loc);
+        }
 
-        #[allow(clippy::single_match)] // TODO: Unsure if we need to fill these out
         match rval {
             Rvalue::Cast(CastKind::PointerCoercion(PointerCoercion::ReifyFnPointer), ref op, _) => {
                 let inst = fn_inst_for_ty(op.ty(self.locals).unwrap(), false)
                     .expect("ReifyFnPointer Cast operand type does not resolve to an instance");
                 let fn_sym = fn_inst_sym(self.tcx, None, Some(&inst));
-                update_link_map(self.link_map, fn_sym, ItemSource(FPTR));
+                self.add_call_edge(fn_sym.as_ref(), CallKind::FnPtr, loc.span());
+                update_link_map(
+                    self.link_map,
+                    self.link_conflicts,
+                    fn_sym,
+                    ItemSource(FPTR),
+                    self.link_inst,
+                );
+                self.reach(MonoItem::Fn(inst));
+            }
+            Rvalue::Cast(CastKind::PointerCoercion(PointerCoercion::Unsize), ref op, target) => {
+                self.visit_unsize(op.ty(self.locals).unwrap(), *target);
             }
             _ => {}
         };
@@ -367,17 +599,16 @@ impl MirVisitor for BodyAnalyzer<'_, '_> {
                     alloc,
                     constant.ty().kind()
                 );
-                alloc
-                    .provenance
-                    .ptrs
-                    .iter()
-                    .for_each(|(offset, prov)| collect_alloc(self, constant.ty(), *offset, prov.0));
+                alloc.provenance.ptrs.iter().for_each(|(offset, prov)| {
+                    collect_alloc(self, alloc, constant.ty(), *offset, prov.0)
+                });
             }
             ConstantKind::Ty(ty_const) => {
                 if let TyConstKind::Value(..) = ty_const.kind() {
                     panic!("TyConstKind::Value");
                 }
             }
+            ConstantKind::ZeroSized | ConstantKind::Unevaluated(_) if self.polymorphic => {}
             ConstantKind::ZeroSized => {
                 // Zero-sized constants can represent function items (FnDef) used as values,
                 // e.g. when passing a function pointer to a higher-order function.
@@ -391,10 +622,13 @@ impl MirVisitor for BodyAnalyzer<'_, '_> {
                         if let Some((ty, kind, name)) = fn_sym {
                             update_link_map(
                                 self.link_map,
+                                self.link_conflicts,
                                 Some((ty, kind, name)),
                                 ItemSource(FPTR),
+                                self.link_inst,
                             );
                         }
+                        self.reach(MonoItem::Fn(inst));
                     }
                 }
             }
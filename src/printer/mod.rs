@@ -10,34 +10,28 @@
 //! |--------|----------------|
 //! | [`schema`] | Data model types ([`SmirJson`], [`Item`], [`AllocInfo`], etc.) and type aliases; [`Item`] deliberately excludes `MonoItem` for structural phase separation |
 //! | [`collect`] | Three-phase pipeline: collect items, analyze bodies, assemble final output; phase boundary is enforced structurally via the `(MonoItem, Item)` split |
+//! | [`decode`] | `Decoder`: reading memory allocations as typed values, following the collected layouts |
+//! | [`filter`] | `ItemFilter`: restricting the output to items matching def path globs or reachable from entry points |
+//! | [`upstream`] | `UpstreamBodies`: adding the bodies of upstream functions the crate calls |
 //! | [`items`] | Constructing `(MonoItem, Item)` pairs and extracting debug-level details |
 //! | [`mir_visitor`] | `BodyAnalyzer`: single-pass MIR body traversal collecting calls, allocs, types, spans |
-//! | [`ty_visitor`] | `TyCollector`: recursively collects reachable types with layout info (some special kinds are traversed but not stored) |
+//! | [`ty_visitor`] | `TyCollector`: recursively collects reachable types with layout info (coroutine witnesses are skipped) |
+//! | [`cbor`] | CBOR encoder used for `*.smir.cbor` output, with the same field names and enum tagging as the JSON |
+//! | [`split`] | Split output: an index file plus one file per item |
+//! | [`stable_ids`] | Content-derived replacements for interned IDs (`STABLE_IDS` mode), applied by a remapping serializer |
 //! | [`link_map`] | Function resolution map: type + instance kind to symbol name |
 //! | [`types`] | Type helpers and [`TypeMetadata`](schema::TypeMetadata) construction |
 //! | [`util`] | Name resolution, attribute queries, and small collection utilities |
 
-use std::io::Write;
-use std::{fs::File, io};
+use std::io;
+use std::path::Path;
 
 use crate::compat::middle::ty::TyCtxt;
+use crate::compat::output::output_dest;
+use crate::compat::serde::Serialize;
 use crate::compat::serde_json;
 
 // Macros must be defined before module declarations (textual scoping)
-macro_rules! def_env_var {
-    ($fn_name:ident, $var_name:ident) => {
-        fn $fn_name() -> bool {
-            use std::sync::OnceLock;
-            static VAR: OnceLock<bool> = OnceLock::new();
-            *VAR.get_or_init(|| std::env::var(stringify!($var_name)).is_ok())
-        }
-    };
-}
-
-def_env_var!(debug_enabled, DEBUG);
-def_env_var!(link_items_enabled, LINK_ITEMS);
-def_env_var!(link_instance_enabled, LINK_INST);
-
 macro_rules! debug_log_println {
     ($($args:tt)*) => {
         #[cfg(feature = "debug_log")]
@@ -45,35 +39,72 @@ macro_rules! debug_log_println {
     };
 }
 
+mod cbor;
 mod collect;
+mod decode;
+mod filter;
 mod items;
 mod link_map;
 mod mir_visitor;
 mod schema;
+mod split;
+mod stable_ids;
 mod ty_visitor;
 mod types;
+mod upstream;
 mod util;
 
 // Re-exports preserving the public API
 pub use collect::collect_smir;
+pub use filter::ItemFilter;
 pub use items::MonoItemKind;
-pub use schema::{AllocInfo, FnSymType, Item, LinkMapKey, SmirJson, TypeMetadata};
-pub(crate) use util::hash;
+pub use schema::{
+    AdtRepr, AllocInfo, CallEdge, CallKind, DecodedValue, EnumLayout, EnumTag, FnSymType,
+    GenericItem, Item, LinkMapKey, OutputFlags, SmirJson, SmirJsonHeader, TypeMetadata,
+    VTableEntry, VTableFn, VTableInfo, VariantLayout, SCHEMA_VERSION,
+};
+pub use upstream::UpstreamBodies;
 
-pub fn emit_smir(tcx: TyCtxt<'_>) {
-    let smir_json =
-        serde_json::to_string(&collect_smir(tcx)).expect("serde_json failed to write result");
+pub use crate::compat::bridge::InstanceKindTag;
+pub(crate) use util::hash;
 
-    match crate::compat::output::mir_output_path(tcx, "smir.json") {
-        crate::compat::output::OutputDest::Stdout => {
-            write!(&io::stdout(), "{}", smir_json).expect("Failed to write smir.json");
+impl SmirJson {
+    /// Serialize to the `*.smir.json` format, applying stable IDs if present.
+    ///
+    /// Writes into `writer` as it serializes, not through an intermediate
+    /// string; pass a buffered writer. This only keeps the encoded document
+    /// out of memory: `self`, with every body, is held in full.
+    pub fn write_json<W: io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        let mut serializer = serde_json::Serializer::new(writer);
+        match &self.stable_ids {
+            Some(ids) => ids.serialize(self, &mut serializer),
+            None => self.serialize(&mut serializer),
         }
-        crate::compat::output::OutputDest::File(path) => {
-            let mut b = io::BufWriter::new(
-                File::create(&path)
-                    .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
-            );
-            write!(b, "{}", smir_json).expect("Failed to write smir.json");
+    }
+
+    /// Serialize to the `*.smir.cbor` format, applying stable IDs if present.
+    ///
+    /// Field names and enum tagging are the same as in the JSON output.
+    pub fn write_cbor<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut serializer = cbor::Serializer::new(writer);
+        match &self.stable_ids {
+            Some(ids) => ids.serialize(self, &mut serializer)?,
+            None => self.serialize(&mut serializer)?,
         }
+        Ok(())
+    }
+
+    /// Serialize to a `*.smir.json` string; see [`SmirJson::write_json`].
+    pub fn to_json(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_json(&mut buf)
+            .expect("serde_json failed to write result");
+        String::from_utf8(buf).expect("serde_json produced invalid UTF-8")
     }
 }
+
+/// Entry point to write the JSON file (to `out` if given, `-` for stdout)
+pub fn emit_smir(tcx: TyCtxt<'_>, flags: &OutputFlags, out: Option<&Path>) {
+    let smir = collect_smir(tcx, flags);
+    output_dest(tcx, out, "smir.json").write_with(|w| Ok(smir.write_json(w)?));
+}
@@ -4,28 +4,40 @@
 //! [`Item`], [`AllocMap`], [`AllocInfo`], [`TypeMetadata`], [`LinkMapKey`],
 //! [`FnSymType`], and serialization helpers.
 
-use crate::compat::bridge::OpaqueInstanceKind;
+use crate::compat::bridge::{InstanceKindRepr, InstanceKindTag};
 use crate::compat::serde;
 use crate::compat::stable_mir;
 
 use std::collections::{HashMap, HashSet};
 
+use super::filter::ItemFilter;
 use super::items::MonoItemKind;
+use super::stable_ids::StableIds;
+use super::upstream::UpstreamBodies;
 use serde::{Serialize, Serializer};
-use stable_mir::abi::LayoutShape;
+use stable_mir::abi::{
+    ArgAbi, CallConvention, FnAbi, LayoutShape, PassMode, Primitive, TagEncoding, WrappingRange,
+};
 use stable_mir::mir::alloc::{AllocId, GlobalAlloc};
 use stable_mir::mir::visit::MirVisitor;
-use stable_mir::mir::Body;
-use stable_mir::ty::{AdtDef, ConstDef, ForeignItemKind, RigidTy};
+use stable_mir::mir::{Body, Safety};
+use stable_mir::ty::{
+    Abi, AdtDef, AliasKind, AliasTy, Binder, ClosureDef, ConstDef, CoroutineDef,
+    ExistentialTraitRef, FnSig, ForeignDef, ForeignItemKind, GenericArgs, Generics, IndexedVal,
+    ParamTy, Pattern, PredicateKind, RigidTy,
+};
 
 // Type aliases
-pub(super) type LinkMap = HashMap<LinkMapKey, (ItemSource, FnSymType)>;
+pub(super) type LinkMap = HashMap<LinkMapKey, (ItemSource, FnSymType, InstanceKindTag)>;
 pub(super) type TyMap =
     HashMap<stable_mir::ty::Ty, (stable_mir::ty::TyKind, Option<stable_mir::abi::LayoutShape>)>;
 pub(super) type SpanMap = HashMap<usize, SourceData>;
+/// Vtables keyed by the allocation holding their entries.
+pub(super) type VTableMap = HashMap<AllocId, VTableInfo>;
 
 /// Wrapper around the alloc-id-to-allocation map that tracks insertion
-/// behavior in debug builds. This serves two purposes:
+/// behavior in debug builds. Each allocation is stored with its pointee
+/// type, or `None` if provenance analysis could not recover it. This serves two purposes:
 ///
 /// 1. Duplicate detection: if the same AllocId is inserted twice, that
 ///    indicates a body was walked more than once (a regression the
@@ -39,7 +51,7 @@ pub(super) type SpanMap = HashMap<usize, SourceData>;
 /// In release builds the tracking fields are compiled out, making this
 /// a zero-cost wrapper.
 pub(super) struct AllocMap {
-    inner: HashMap<stable_mir::mir::alloc::AllocId, (stable_mir::ty::Ty, GlobalAlloc)>,
+    inner: HashMap<stable_mir::mir::alloc::AllocId, (Option<stable_mir::ty::Ty>, GlobalAlloc)>,
     #[cfg(debug_assertions)]
     insert_count: usize,
     #[cfg(debug_assertions)]
@@ -64,7 +76,7 @@ impl AllocMap {
     pub fn insert(
         &mut self,
         key: stable_mir::mir::alloc::AllocId,
-        value: (stable_mir::ty::Ty, GlobalAlloc),
+        value: (Option<stable_mir::ty::Ty>, GlobalAlloc),
     ) {
         #[cfg(debug_assertions)]
         {
@@ -81,7 +93,7 @@ impl AllocMap {
     ) -> impl Iterator<
         Item = (
             stable_mir::mir::alloc::AllocId,
-            (stable_mir::ty::Ty, GlobalAlloc),
+            (Option<stable_mir::ty::Ty>, GlobalAlloc),
         ),
     > {
         self.inner.into_iter()
@@ -162,10 +174,44 @@ impl MirVisitor for AllocIdCollector<'_> {
 pub(super) const ITEM: u8 = 1 << 0;
 pub(super) const TERM: u8 = 1 << 1;
 pub(super) const FPTR: u8 = 1 << 2;
+pub(super) const VTBL: u8 = 1 << 3;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(super) struct ItemSource(pub u8);
 
+impl ItemSource {
+    /// The names of the sources, as serialized.
+    pub(super) fn names(&self) -> Vec<&'static str> {
+        [
+            (ITEM, "Item"),
+            (TERM, "Term"),
+            (FPTR, "Fptr"),
+            (VTBL, "Vtbl"),
+        ]
+        .into_iter()
+        .filter(|(bit, _)| self.0 & bit != 0)
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+/// A symbol that was found for a link map key which already had a different
+/// one. This can only happen with `LINK_INST`, where a key names a single
+/// instance; the symbol found first is kept.
+#[derive(Serialize, Clone, Debug)]
+pub(super) struct LinkMapConflict {
+    pub key: LinkMapKey,
+    pub kept: LinkMapCandidate,
+    pub rejected: LinkMapCandidate,
+}
+
+/// A symbol for a link map key and where it was found.
+#[derive(Serialize, Clone, Debug)]
+pub(super) struct LinkMapCandidate {
+    pub symbol: FnSymType,
+    pub sources: ItemSource,
+}
+
 impl Serialize for ItemSource {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -173,15 +219,9 @@ impl Serialize for ItemSource {
     {
         use serde::ser::SerializeSeq;
         let mut seq = serializer.serialize_seq(None)?;
-        if self.0 & ITEM != 0u8 {
-            seq.serialize_element(&"Item")?
-        };
-        if self.0 & TERM != 0u8 {
-            seq.serialize_element(&"Term")?
-        };
-        if self.0 & FPTR != 0u8 {
-            seq.serialize_element(&"Fptr")?
-        };
+        for name in self.names() {
+            seq.serialize_element(name)?;
+        }
         seq.end()
     }
 }
@@ -191,7 +231,7 @@ impl Serialize for ItemSource {
 /// Each function encountered during MIR traversal is categorized as one of:
 /// a no-op shim (empty body), a compiler intrinsic, or a normal function
 /// with a mangled symbol name.
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FnSymType {
     /// An empty shim (no-op); the string is unused.
     NoOpSym(String),
@@ -204,15 +244,12 @@ pub enum FnSymType {
 /// Key into the link-time function resolution map.
 ///
 /// Pairs a Stable MIR type (always an `FnDef`) with an optional internal
-/// `InstanceKind` for disambiguation. When serialized, the representation
-/// depends on the `LINK_INST` environment variable: with it set, both
-/// components are emitted as a 2-tuple; without it, only the type index
-/// is written.
+/// `InstanceKind` for disambiguation. The instance kind is only recorded
+/// with `LINK_INST`; when present, both components are serialized as a
+/// 2-tuple (the instance kind as its variant with the items and types it
+/// refers to), otherwise only the type index is written.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct LinkMapKey(
-    pub stable_mir::ty::Ty,
-    pub(super) Option<OpaqueInstanceKind>,
-);
+pub struct LinkMapKey(pub stable_mir::ty::Ty, pub(super) Option<InstanceKindRepr>);
 
 impl Serialize for LinkMapKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -220,17 +257,86 @@ impl Serialize for LinkMapKey {
         S: Serializer,
     {
         use serde::ser::SerializeTuple;
-        if super::link_instance_enabled() {
-            let mut tup = serializer.serialize_tuple(2)?;
-            tup.serialize_element(&self.0)?;
-            tup.serialize_element(&format!("{:?}", self.1))?;
-            tup.end()
-        } else {
-            <stable_mir::ty::Ty as Serialize>::serialize(&self.0, serializer)
+        match &self.1 {
+            Some(kind) => {
+                let mut tup = serializer.serialize_tuple(2)?;
+                tup.serialize_element(&self.0)?;
+                tup.serialize_element(kind)?;
+                tup.end()
+            }
+            None => <stable_mir::ty::Ty as Serialize>::serialize(&self.0, serializer),
         }
     }
 }
 
+/// The vtable of a concrete type used as a trait object.
+///
+/// `entries` follows the vtable's layout, one entry per pointer-sized slot,
+/// so the index `n` of an `InstanceKind::Virtual` call selects `entries[n]`.
+#[derive(Serialize)]
+pub struct VTableInfo {
+    /// The concrete type behind the trait object.
+    pub ty: stable_mir::ty::Ty,
+    /// The principal trait; `None` for a `dyn` of auto traits only.
+    pub trait_ref: Option<Binder<ExistentialTraitRef>>,
+    /// The path of the principal trait.
+    pub trait_name: Option<String>,
+    /// Size and alignment of `ty`, as stored in the vtable.
+    pub size: u64,
+    pub align: u64,
+    pub entries: Vec<VTableEntry>,
+}
+
+/// A slot of a [`VTableInfo`].
+#[derive(Serialize)]
+pub enum VTableEntry {
+    /// The drop glue of the type, absent if it needs no drop.
+    MetadataDropInPlace(Option<VTableFn>),
+    MetadataSize,
+    MetadataAlign,
+    /// A method that cannot be called on a trait object (null slot).
+    Vacant,
+    Method(VTableFn),
+    /// A pointer to the vtable of the same type as the given supertrait.
+    TraitVPtr(Binder<ExistentialTraitRef>),
+}
+
+/// A function in a vtable slot: its key in the `functions` map and symbol.
+/// Closure bodies have no key, as their type is not a function definition,
+/// nor have reify and vtable shims without `LINK_INST`.
+#[derive(Serialize)]
+pub struct VTableFn {
+    pub key: Option<LinkMapKey>,
+    pub symbol: FnSymType,
+}
+
+/// A call site (or function pointer) in the body of an item, linking the
+/// caller to the function it calls.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CallEdge {
+    /// Symbol name of the calling item.
+    pub caller: String,
+    pub callee: FnSymType,
+    pub kind: CallKind,
+    /// The call terminator, or the statement creating the function pointer.
+    pub span: stable_mir::ty::Span,
+}
+
+/// How a [`CallEdge`] reaches its callee.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CallKind {
+    /// A `Call` terminator of a statically known function.
+    Direct,
+    /// A `ReifyFnPointer` cast: the caller takes a pointer to the callee,
+    /// which calls through the pointer may then reach.
+    FnPtr,
+    /// A `Drop` terminator, calling the drop glue of the dropped type.
+    DropGlue,
+    /// A call through a `dyn Trait`; `idx` is the slot of the receiver's
+    /// vtable holding the method that is actually called.
+    Virtual { idx: usize },
+}
+
 // Item details (debug info)
 #[derive(Serialize, Clone)]
 pub(super) struct BodyDetails {
@@ -281,6 +387,9 @@ pub(super) struct ForeignModule {
 pub struct Item {
     pub symbol_name: String,
     pub mono_item_kind: MonoItemKind,
+    /// The upstream crate the body was taken from, for functions added by
+    /// `--smir-upstream`; `None` for the local crate's mono items.
+    pub external_crate: Option<String>,
     details: Option<ItemDetails>,
 }
 
@@ -293,6 +402,7 @@ impl Item {
         Item {
             symbol_name,
             mono_item_kind,
+            external_crate: None,
             details,
         }
     }
@@ -310,6 +420,14 @@ impl Item {
             _ => None,
         }
     }
+
+    /// The ABI of a function item, if available.
+    pub(super) fn fn_abi(&self) -> Option<&FnAbiInfo> {
+        match &self.mono_item_kind {
+            MonoItemKind::MonoItemFn { fn_abi, .. } => fn_abi.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Item {
@@ -343,6 +461,23 @@ impl Ord for Item {
     }
 }
 
+/// A generic function of the local crate, with its polymorphic body
+/// (`--smir-polymorphic`).
+///
+/// Types in the body may mention the item's generic parameters, which
+/// appear in `types` as [`TypeMetadata::ParamType`].
+#[derive(Serialize, Clone)]
+pub struct GenericItem {
+    pub name: String,
+    pub id: stable_mir::DefId,
+    /// Generics of the item and its parents (e.g. the impl block of a
+    /// method), outermost first; a `ParamTy` index counts through all of them.
+    pub generics: Vec<Generics>,
+    /// Predicates in scope in the body, including those of the parents.
+    pub predicates: Vec<(PredicateKind, stable_mir::ty::Span)>,
+    pub body: Body,
+}
+
 /// A recorded global allocation encountered during MIR traversal.
 ///
 /// Captures the allocation id, the pointee type (as best as can be determined
@@ -351,20 +486,27 @@ impl Ord for Item {
 #[derive(Serialize)]
 pub struct AllocInfo {
     alloc_id: AllocId,
+    /// `Ty` 0 if provenance analysis could not recover the pointee type.
     ty: stable_mir::ty::Ty,
     global_alloc: GlobalAlloc,
+    /// The contents of a memory allocation read as a value of type `ty`;
+    /// `None` for other allocations, if `ty` has no layout, or if the
+    /// pointee type was not recovered.
+    decoded: Option<DecodedValue>,
 }
 
 impl AllocInfo {
     pub(super) fn new(
         alloc_id: AllocId,
-        ty: stable_mir::ty::Ty,
+        ty: Option<stable_mir::ty::Ty>,
         global_alloc: GlobalAlloc,
+        decoded: Option<DecodedValue>,
     ) -> Self {
         AllocInfo {
             alloc_id,
-            ty,
+            ty: ty.unwrap_or_else(|| stable_mir::ty::Ty::to_val(0)),
             global_alloc,
+            decoded,
         }
     }
 
@@ -382,6 +524,48 @@ impl AllocInfo {
     pub fn global_alloc(&self) -> &GlobalAlloc {
         &self.global_alloc
     }
+
+    /// The allocation's contents as a typed value, if decoded.
+    pub fn decoded(&self) -> Option<&DecodedValue> {
+        self.decoded.as_ref()
+    }
+}
+
+/// A value read from the bytes of an allocation, following the layout of
+/// its type (see [`decode`](super::decode)).
+///
+/// Parts that cannot be read as a value of their type (uninitialized or
+/// partially initialized scalars, unions, and types without a layout) are
+/// given as their raw `Bytes`, `null` marking an uninitialized byte.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum DecodedValue {
+    Bool(bool),
+    Int(i128),
+    Uint(u128),
+    /// A float, in Rust's (round-tripping) decimal notation, e.g. `-0.5`,
+    /// `inf` or `NaN`.
+    Float(String),
+    Char(char),
+    /// The contents of a `str`.
+    Str(String),
+    /// A pointer, reference, or function pointer. `alloc_id` is the
+    /// allocation it points into (absent for an integer address), `offset`
+    /// the byte offset within it (or the address). Wide pointers carry their
+    /// `metadata`: a slice length, or a pointer to a vtable.
+    Ptr {
+        alloc_id: Option<AllocId>,
+        offset: u64,
+        metadata: Option<Box<DecodedValue>>,
+    },
+    /// The fields of a struct, tuple or closure, in declaration order.
+    Aggregate(Vec<DecodedValue>),
+    /// An enum value: the index of its variant and that variant's fields.
+    Enum {
+        variant: usize,
+        fields: Vec<DecodedValue>,
+    },
+    Array(Vec<DecodedValue>),
+    Bytes(Vec<Option<u8>>),
 }
 
 /// Structured metadata about a Rust type, suitable for execution or verification.
@@ -395,25 +579,43 @@ pub enum TypeMetadata {
     PrimitiveType(RigidTy),
     EnumType {
         name: String,
-        // adt_def serializes as a non-deterministic interned index (DefId), but
-        // downstream consumers need it to cross-reference AggregateKind::Adt in
-        // MIR bodies with the type metadata here. We can't stabilize it without
-        // also controlling AggregateKind serialization (which comes from stable_mir).
+        // By default adt_def serializes as a non-deterministic interned index
+        // (DefId), but downstream consumers need it to cross-reference
+        // AggregateKind::Adt in MIR bodies with the type metadata here. With
+        // STABLE_IDS it becomes the DefPathHash-derived ID, as does the DefId
+        // in AggregateKind, so both stay consistent and reproducible.
         adt_def: AdtDef,
         discriminants: Vec<u128>,
         fields: Vec<Vec<stable_mir::ty::Ty>>,
+        /// Variant names, in declaration order.
+        variant_names: Vec<String>,
+        /// Field names of each variant; tuple fields are named by position.
+        field_names: Vec<Vec<String>>,
+        /// The arguments the enum is instantiated with.
+        generic_args: GenericArgs,
+        repr: AdtRepr,
         layout: Option<LayoutShape>,
+        /// The tag and variant layouts, derived from `layout`.
+        enum_layout: Option<EnumLayout>,
     },
     StructType {
         name: String,
         adt_def: AdtDef,
         fields: Vec<stable_mir::ty::Ty>,
+        /// Field names; tuple fields are named by position.
+        field_names: Vec<String>,
+        generic_args: GenericArgs,
+        repr: AdtRepr,
         layout: Option<LayoutShape>,
     },
     UnionType {
         name: String,
         adt_def: AdtDef,
         fields: Vec<stable_mir::ty::Ty>,
+        /// Field names; tuple fields are named by position.
+        field_names: Vec<String>,
+        generic_args: GenericArgs,
+        repr: AdtRepr,
         layout: Option<LayoutShape>,
     },
     ArrayType {
@@ -439,13 +641,274 @@ pub enum TypeMetadata {
         name: String,
         layout: Option<LayoutShape>,
     },
-    FunType(String),
+    /// A function definition (a zero-sized type) or function pointer.
+    FnSigType {
+        name: String,
+        sig: FnSignature,
+        layout: Option<LayoutShape>,
+    },
+    /// A closure; its captured variables are the fields of its layout.
+    ClosureType {
+        name: String,
+        closure_def: ClosureDef,
+        sig: FnSignature,
+        upvar_tys: Vec<stable_mir::ty::Ty>,
+        layout: Option<LayoutShape>,
+    },
+    /// A coroutine (the state machine of an `async` block or function). The
+    /// captured variables are the fields common to all states; each entry of
+    /// `variants` lists the `saved_locals` that a state variant of the
+    /// layout holds, starting with the unresumed, returned and poisoned
+    /// states (which hold none).
+    CoroutineType {
+        name: String,
+        coroutine_def: CoroutineDef,
+        upvar_tys: Vec<stable_mir::ty::Ty>,
+        saved_locals: Vec<stable_mir::ty::Ty>,
+        variants: Vec<Vec<usize>>,
+        layout: Option<LayoutShape>,
+    },
+    /// A pattern type: `base` restricted to the values matching `pattern`.
+    PatternType {
+        base: stable_mir::ty::Ty,
+        pattern: Pattern,
+        layout: Option<LayoutShape>,
+    },
+    /// An extern type, which has no known size.
+    ForeignType {
+        name: String,
+        foreign_def: ForeignDef,
+        layout: Option<LayoutShape>,
+    },
     VoidType,
+    /// A generic type parameter, only found in the bodies of `generic_items`.
+    ParamType(ParamTy),
+    /// An associated type or opaque type that could not be normalized,
+    /// only found in the bodies of `generic_items`.
+    AliasType {
+        name: String,
+        kind: AliasKind,
+        alias: AliasTy,
+    },
+}
+
+/// How a function is called: [`stable_mir::abi::FnAbi`] with the layout of
+/// each argument inlined, since its interned `Layout` handle resolves
+/// against no table of the output.
+#[derive(Serialize, Clone)]
+pub struct FnAbiInfo {
+    pub args: Vec<ArgAbiInfo>,
+    pub ret: ArgAbiInfo,
+    pub fixed_count: u32,
+    pub conv: CallConvention,
+    pub c_variadic: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ArgAbiInfo {
+    pub ty: stable_mir::ty::Ty,
+    pub layout: LayoutShape,
+    pub mode: PassMode,
+}
+
+impl From<FnAbi> for FnAbiInfo {
+    fn from(fn_abi: FnAbi) -> Self {
+        FnAbiInfo {
+            args: fn_abi.args.into_iter().map(ArgAbiInfo::from).collect(),
+            ret: fn_abi.ret.into(),
+            fixed_count: fn_abi.fixed_count,
+            conv: fn_abi.conv,
+            c_variadic: fn_abi.c_variadic,
+        }
+    }
+}
+
+impl From<ArgAbi> for ArgAbiInfo {
+    fn from(arg: ArgAbi) -> Self {
+        ArgAbiInfo {
+            ty: arg.ty,
+            layout: arg.layout.shape(),
+            mode: arg.mode,
+        }
+    }
+}
+
+impl FnAbiInfo {
+    /// The argument types followed by the return type.
+    pub(super) fn tys(&self) -> Vec<stable_mir::ty::Ty> {
+        self.args
+            .iter()
+            .chain([&self.ret])
+            .map(|arg| arg.ty)
+            .collect()
+    }
+}
+
+/// The signature of a function type, with lifetimes erased.
+///
+/// A closure takes its arguments as a single tuple, with the `RustCall` ABI.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FnSignature {
+    pub inputs: Vec<stable_mir::ty::Ty>,
+    pub output: stable_mir::ty::Ty,
+    pub abi: Abi,
+    /// Whether the function takes C variadic arguments (`...`) after `inputs`.
+    pub c_variadic: bool,
+    pub safety: Safety,
+}
+
+impl From<FnSig> for FnSignature {
+    fn from(sig: FnSig) -> Self {
+        let output = sig.output();
+        let mut inputs = sig.inputs_and_output;
+        inputs.pop();
+        FnSignature {
+            inputs,
+            output,
+            abi: sig.abi,
+            c_variadic: sig.c_variadic,
+            safety: sig.safety,
+        }
+    }
+}
+
+/// The `#[repr]` options of an ADT, as far as they affect its layout.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AdtRepr {
+    /// `#[repr(C)]`
+    pub c: bool,
+    /// `#[repr(transparent)]`
+    pub transparent: bool,
+    /// `#[repr(simd)]`
+    pub simd: bool,
+    /// The integer type of `#[repr(u8)]` etc., as an `Int` or `Uint` [`RigidTy`](stable_mir::ty::RigidTy).
+    pub int: Option<stable_mir::ty::RigidTy>,
+    /// `#[repr(packed(N))]`, in bytes.
+    pub packed: Option<u64>,
+    /// `#[repr(align(N))]`, in bytes.
+    pub align: Option<u64>,
+}
+
+/// How the values of an enum are laid out in memory, derived from its
+/// [`LayoutShape`] so that consumers need not re-derive rustc's layout rules.
+///
+/// To read a value, read the tag and select the variant whose `tag_value`
+/// equals it (or, for a niche encoding, the untagged variant if none does);
+/// to write one, write the variant's fields and then its `tag_value`, if any.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EnumLayout {
+    /// The tag, or `None` if at most one variant is inhabited (and laid out
+    /// without a tag).
+    pub tag: Option<EnumTag>,
+    /// The layout of each variant, in declaration order; `None` for the
+    /// uninhabited variants of an enum without a tag, which have none.
+    pub variants: Vec<Option<VariantLayout>>,
+}
+
+/// Where an enum's tag is stored and how it encodes the variant.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EnumTag {
+    /// Byte offset of the tag in the enum.
+    pub offset: usize,
+    /// Size of the tag in bytes.
+    pub size: usize,
+    pub primitive: Primitive,
+    /// The tag values that are valid (wrapping around if `start > end`).
+    pub valid_range: WrappingRange,
+    /// `Direct`: the tag holds the discriminant, truncated to its size.
+    /// `Niche`: the tag is a field of `untagged_variant` holding a value
+    /// that field cannot take for the variants in `niche_variants`; any
+    /// other value means `untagged_variant`.
+    pub encoding: TagEncoding,
+}
+
+/// The layout of one enum variant.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct VariantLayout {
+    /// Byte offsets of the variant's fields, in declaration order.
+    pub field_offsets: Vec<usize>,
+    /// The tag value selecting this variant; `None` for the untagged
+    /// variant of a niche encoding, and for enums without a tag.
+    pub tag_value: Option<u128>,
+    pub uninhabited: bool,
 }
 
 /// Span location data: `(filename, start_line, start_col, end_line, end_col)`.
 pub type SourceData = crate::compat::spans::SourceData;
 
+/// Revision of the output format. Bump whenever the shape of [`SmirJson`]
+/// (or anything it contains) changes, so consumers can reject files they
+/// do not understand.
+pub const SCHEMA_VERSION: u32 = 14;
+
+/// Identifies the output format revision, the producer, and the flags that
+/// affect the shape of the output. Serialized first, so consumers can check
+/// it before parsing the rest of the file.
+#[derive(Serialize, Clone, Debug)]
+pub struct SmirJsonHeader {
+    pub schema_version: u32,
+    pub producer: &'static str,
+    pub producer_version: &'static str,
+    /// `rustc -vV` first line of the toolchain the producer was built against.
+    pub rustc_version: &'static str,
+    pub rustc_commit: &'static str,
+    pub flags: OutputFlags,
+}
+
+/// Options that change what the output contains. Set from the command line
+/// (`--smir-link-items`, ...) or the corresponding environment variables,
+/// passed to [`collect_smir`](super::collect_smir), and recorded in the
+/// [`SmirJsonHeader`].
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputFlags {
+    /// Add link map entries for every monomorphic item (`LINK_ITEMS`).
+    pub link_items: bool,
+    /// Key the link map by type and instance kind (`LINK_INST`).
+    pub link_inst: bool,
+    /// Serialize extra debug information (`DEBUG`).
+    pub debug: bool,
+    /// Replace interned indices with content-derived IDs (`STABLE_IDS`).
+    pub stable_ids: bool,
+    /// Restrict the output to some of the crate's items (command line only).
+    pub filter: ItemFilter,
+    /// Also emit the polymorphic bodies of generic functions (command line
+    /// only).
+    pub polymorphic: bool,
+    /// Add the bodies of upstream functions the crate calls (command line
+    /// only).
+    pub upstream: Option<UpstreamBodies>,
+}
+
+impl OutputFlags {
+    /// Flags from the `LINK_ITEMS`, `LINK_INST`, `DEBUG` and `STABLE_IDS`
+    /// environment variables; each is enabled if the variable is set at all.
+    pub fn from_env() -> Self {
+        let set = |var| std::env::var_os(var).is_some();
+        OutputFlags {
+            link_items: set("LINK_ITEMS"),
+            link_inst: set("LINK_INST"),
+            debug: set("DEBUG"),
+            stable_ids: set("STABLE_IDS"),
+            filter: ItemFilter::default(),
+            polymorphic: false,
+            upstream: None,
+        }
+    }
+}
+
+impl SmirJsonHeader {
+    pub(super) fn new(flags: &OutputFlags) -> Self {
+        SmirJsonHeader {
+            schema_version: SCHEMA_VERSION,
+            producer: env!("CARGO_PKG_NAME"),
+            producer_version: env!("CARGO_PKG_VERSION"),
+            rustc_version: env!("SMIR_RUSTC_VERSION"),
+            rustc_commit: env!("SMIR_RUSTC_COMMIT"),
+            flags: flags.clone(),
+        }
+    }
+}
+
 /// Top-level output structure serialized as the `*.smir.json` file.
 ///
 /// Contains all information extracted from the crate's Stable MIR:
@@ -456,26 +919,65 @@ pub type SourceData = crate::compat::spans::SourceData;
 /// sorted where applicable to improve output determinism across runs.
 #[derive(Serialize)]
 pub struct SmirJson {
+    pub header: SmirJsonHeader,
     pub name: String,
     pub crate_id: u64,
     pub allocs: Vec<AllocInfo>,
-    pub functions: Vec<(LinkMapKey, FnSymType)>,
+    /// The symbol of every function the bodies call or refer to, with the
+    /// kind of the instance it names.
+    pub functions: Vec<(LinkMapKey, FnSymType, InstanceKindTag)>,
+    /// Every vtable created for a trait object, by an unsizing cast or in a
+    /// constant.
+    pub vtables: Vec<VTableInfo>,
+    /// The call graph: every call, drop and function pointer in the bodies
+    /// of `items`.
+    pub calls: Vec<CallEdge>,
     pub uneval_consts: Vec<(ConstDef, String)>,
     pub items: Vec<Item>,
+    /// Generic functions of the local crate; empty unless the
+    /// `polymorphic` flag is set.
+    pub generic_items: Vec<GenericItem>,
     pub types: Vec<(stable_mir::ty::Ty, TypeMetadata)>,
+    #[serde(serialize_with = "serialize_span_table")]
     pub spans: Vec<(usize, SourceData)>,
     pub debug: Option<SmirJsonDebugInfo>,
     pub machine: stable_mir::target::MachineInfo,
+    /// Content-derived replacements for interned IDs, present when the
+    /// `STABLE_IDS` environment variable is set. Applied at emission time.
+    #[serde(skip)]
+    pub stable_ids: Option<StableIds>,
+}
+
+/// Serialize span table keys as `Span` newtypes (which serialize the same as
+/// the bare index) so that ID remapping recognises them.
+pub(super) fn serialize_span_table<S: Serializer>(
+    spans: &[(usize, SourceData)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeSeq;
+    struct SpanKey(usize);
+    impl Serialize for SpanKey {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_newtype_struct("Span", &self.0)
+        }
+    }
+    let mut seq = serializer.serialize_seq(Some(spans.len()))?;
+    for (span, data) in spans {
+        seq.serialize_element(&(SpanKey(*span), data))?;
+    }
+    seq.end()
 }
 
 /// Extra debug information included when the `DEBUG` environment variable is set.
 ///
 /// Contains the provenance of each function map entry (whether it came from
-/// an item, a terminator call, or a function pointer cast), the raw type map,
-/// and foreign module details.
+/// an item, a terminator call, or a function pointer cast), the symbols that
+/// conflicted with a function map entry, the raw type map, and foreign
+/// module details.
 #[derive(Serialize)]
 pub struct SmirJsonDebugInfo {
     pub(super) fn_sources: Vec<(LinkMapKey, ItemSource)>,
+    pub(super) link_conflicts: Vec<LinkMapConflict>,
     pub(super) types: TyMap,
     pub(super) foreign_modules: Vec<(String, Vec<ForeignModule>)>,
 }
@@ -486,6 +988,7 @@ pub struct SmirJsonDebugInfo {
 /// `CollectedCrate` structurally cannot call `inst.body()` or re-enter rustc.
 pub(super) struct CollectedCrate {
     pub items: Vec<Item>,
+    pub generic_items: Vec<GenericItem>,
     pub unevaluated_consts: HashMap<stable_mir::ty::ConstDef, String>,
 }
 
@@ -494,4 +997,7 @@ pub(super) struct DerivedInfo {
     pub allocs: AllocMap,
     pub types: TyMap,
     pub spans: SpanMap,
+    pub vtables: VTableMap,
+    pub call_edges: Vec<CallEdge>,
+    pub link_conflicts: Vec<LinkMapConflict>,
 }
@@ -0,0 +1,188 @@
+//! Split output: a directory with one file per item.
+//!
+//! Consumers that only need a few function bodies should not have to parse
+//! the whole crate. [`SmirJson::write_split`] writes the same data as the
+//! `*.smir.json` file as a directory:
+//!
+//! ```text
+//! main.smir/
+//!   .smir-split       marker: this directory was written by this tool
+//!   index.json        every top-level field except `items`, which is
+//!                     replaced by a manifest of `{symbol_name, file}` entries
+//!   items/<file>      one serialized `Item` per entry
+//! ```
+//!
+//! Writing over an earlier split output replaces its item files. An `items`
+//! directory without the marker next to it belongs to someone else, and is
+//! left alone: writing fails instead.
+//!
+//! The shared tables (`types`, `allocs`, `spans`, `functions`, `calls`) and the
+//! `generic_items` stay in the index. Item files are named after the symbol name when it is short and
+//! consists of filename-safe characters only; other names are sanitized,
+//! truncated, and suffixed with the item's position, so the manifest is the
+//! only reliable way to find an item's file.
+//!
+//! With stable IDs, the handles that are numbered densely in output order
+//! are numbered exactly as in the single file, so joining the index with the
+//! item files reproduces `*.smir.json`.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::compat::bridge::InstanceKindTag;
+use crate::compat::serde;
+use crate::compat::serde_json;
+use crate::compat::stable_mir;
+
+use serde::{Serialize, Serializer};
+use stable_mir::ty::ConstDef;
+
+use super::schema::{
+    serialize_span_table, AllocInfo, CallEdge, FnSymType, GenericItem, LinkMapKey, SmirJson,
+    SmirJsonDebugInfo, SmirJsonHeader, SourceData, TypeMetadata, VTableInfo,
+};
+use super::stable_ids::Emitter;
+
+/// Name of the index file inside a split output directory.
+const INDEX_FILE: &str = "index.json";
+/// Name of the item subdirectory inside a split output directory.
+const ITEMS_DIR: &str = "items";
+/// Name of the file marking a directory as split output.
+const MARKER_FILE: &str = ".smir-split";
+
+/// Longest symbol name used verbatim as a file name (leaving room for the
+/// extension within the common 255-byte limit).
+const MAX_FILE_STEM: usize = 200;
+
+#[derive(Serialize)]
+struct SplitIndex<'a> {
+    header: &'a SmirJsonHeader,
+    name: &'a str,
+    crate_id: u64,
+    allocs: &'a [AllocInfo],
+    functions: &'a [(LinkMapKey, FnSymType, InstanceKindTag)],
+    vtables: &'a [VTableInfo],
+    calls: &'a [CallEdge],
+    uneval_consts: &'a [(ConstDef, String)],
+    items: Vec<ItemEntry<'a>>,
+    generic_items: &'a [GenericItem],
+    types: &'a [(stable_mir::ty::Ty, TypeMetadata)],
+    #[serde(serialize_with = "serialize_spans")]
+    spans: &'a [(usize, SourceData)],
+    debug: Option<&'a SmirJsonDebugInfo>,
+    machine: &'a stable_mir::target::MachineInfo,
+}
+
+#[derive(Serialize)]
+struct ItemEntry<'a> {
+    symbol_name: &'a str,
+    /// Path of the item file, relative to the items directory.
+    file: String,
+}
+
+fn serialize_spans<S: Serializer>(
+    spans: &&[(usize, SourceData)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_span_table(spans, serializer)
+}
+
+/// File name for the item at `index` with the given symbol name.
+fn item_file_name(symbol_name: &str, index: usize) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$');
+    if !symbol_name.is_empty()
+        && symbol_name.len() <= MAX_FILE_STEM
+        && !symbol_name.starts_with('.')
+        && symbol_name.chars().all(safe)
+    {
+        return format!("{symbol_name}.json");
+    }
+    // `-` never occurs in a verbatim name, so these cannot collide with one.
+    let stem: String = symbol_name
+        .chars()
+        .map(|c| if safe(c) { c } else { '_' })
+        .take(MAX_FILE_STEM - 20)
+        .collect();
+    format!("{stem}-{index}.json")
+}
+
+fn write_file<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+    emitter: Option<&Emitter<'_>>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut serializer = serde_json::Serializer::new(&mut writer);
+    match emitter {
+        Some(emitter) => emitter.serialize(value, &mut serializer)?,
+        None => value.serialize(&mut serializer)?,
+    }
+    writer.flush()
+}
+
+impl SmirJson {
+    /// Write split output into `dir`, creating it if necessary; see the
+    /// [module documentation](self). The items directory of an earlier split
+    /// output is replaced; any other existing items directory is an error.
+    pub fn write_split(&self, dir: &Path) -> io::Result<()> {
+        let items_dir = dir.join(ITEMS_DIR);
+        let marker = dir.join(MARKER_FILE);
+        if items_dir.exists() {
+            if !marker.is_file() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} exists and is not from a split output; not replacing it",
+                        items_dir.display()
+                    ),
+                ));
+            }
+            fs::remove_dir_all(&items_dir)?;
+        }
+        fs::create_dir_all(&items_dir)?;
+        fs::write(&marker, "")?;
+
+        let emitter = self.stable_ids.as_ref().map(|ids| ids.emitter());
+        if let Some(emitter) = &emitter {
+            // Number the handles in the fields before `items` first, as the
+            // single-file output does.
+            let prefix = (
+                &self.allocs,
+                &self.functions,
+                &self.vtables,
+                &self.calls,
+                &self.uneval_consts,
+            );
+            emitter.serialize(&prefix, &mut serde_json::Serializer::new(io::sink()))?;
+        }
+
+        let mut entries = Vec::with_capacity(self.items.len());
+        for (index, item) in self.items.iter().enumerate() {
+            let file = item_file_name(&item.symbol_name, index);
+            write_file(&items_dir.join(&file), item, emitter.as_ref())?;
+            entries.push(ItemEntry {
+                symbol_name: &item.symbol_name,
+                file,
+            });
+        }
+
+        let index = SplitIndex {
+            header: &self.header,
+            name: &self.name,
+            crate_id: self.crate_id,
+            allocs: &self.allocs,
+            functions: &self.functions,
+            vtables: &self.vtables,
+            calls: &self.calls,
+            uneval_consts: &self.uneval_consts,
+            items: entries,
+            generic_items: &self.generic_items,
+            types: &self.types,
+            spans: &self.spans,
+            debug: self.debug.as_ref(),
+            machine: &self.machine,
+        };
+        write_file(&dir.join(INDEX_FILE), &index, emitter.as_ref())
+    }
+}
@@ -0,0 +1,670 @@
+//! Content-derived identifiers for interned compiler handles.
+//!
+//! Stable MIR serializes `Ty`, `AllocId`, `DefId` (and with it every `*Def`
+//! wrapper such as `AdtDef` or `FnDef`), `Span`, and a few other handles as
+//! indices into per-invocation interning tables. Those indices depend on the
+//! order in which the compiler happened to intern things, so two runs over the
+//! same source produce different numbers, and numbers from two crates cannot
+//! be compared at all.
+//!
+//! When the `STABLE_IDS` environment variable is set, [`StableIds::build`]
+//! computes a content-derived replacement for every such handle reachable
+//! from the [`SmirJson`] value:
+//!
+//! | Handle | Derived from |
+//! |--------|--------------|
+//! | `Ty` | rustc's stable type hash (the one behind `TypeId`) |
+//! | `DefId` | the definition's `DefPathHash` |
+//! | `AllocId` | the allocation's content: pointee type, bytes, alignment and (recursively) the IDs of its provenance targets; the static, vtable, or function symbol otherwise |
+//! | `Span` | the resolved source location |
+//! | `TyConstId`, `MirConstId`, `InstanceDef` | dense numbering in output order (these are never join keys) |
+//!
+//! The substitution happens at serialization time: [`IdRemapSerializer`]
+//! wraps the output serializer and intercepts `serialize_newtype_struct` calls
+//! by newtype name, which is how serde's derive encodes these single-field
+//! index wrappers. Everything else is forwarded unchanged, so the output shape
+//! is identical to the default mode.
+//!
+//! IDs are masked to 53 bits so they survive JSON tooling that parses numbers
+//! as IEEE-754 doubles (jq, JavaScript).
+
+use crate::compat::hashing::stable_hash;
+use crate::compat::middle::ty::TyCtxt;
+use crate::compat::serde;
+use crate::compat::serde_json;
+use crate::compat::stable_mir;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use serde::ser::{
+    Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use stable_mir::mir::alloc::{AllocId, GlobalAlloc};
+use stable_mir::ty::{GenericArgKind, IndexedVal};
+use stable_mir::CrateDef;
+
+use super::schema::{SmirJson, SourceData};
+
+const ID_MASK: u64 = (1 << 53) - 1;
+
+/// The interned handle types that get replaced, keyed by their newtype name.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum IdKind {
+    Ty,
+    DefId,
+    AllocId,
+    Span,
+    TyConstId,
+    MirConstId,
+    InstanceDef,
+}
+
+impl IdKind {
+    fn from_newtype_name(name: &str) -> Option<Self> {
+        match name {
+            "Ty" => Some(IdKind::Ty),
+            "DefId" => Some(IdKind::DefId),
+            "AllocId" => Some(IdKind::AllocId),
+            "Span" => Some(IdKind::Span),
+            "TyConstId" => Some(IdKind::TyConstId),
+            "MirConstId" => Some(IdKind::MirConstId),
+            "InstanceDef" => Some(IdKind::InstanceDef),
+            _ => None,
+        }
+    }
+
+    /// Kinds whose replacement is a content hash (as opposed to dense
+    /// numbering in output order).
+    fn is_content_derived(self) -> bool {
+        matches!(
+            self,
+            IdKind::Ty | IdKind::DefId | IdKind::AllocId | IdKind::Span
+        )
+    }
+}
+
+/// Maps a raw interned index to the value that should be written instead.
+trait IdMapper {
+    fn map(&self, kind: IdKind, raw: usize) -> u64;
+}
+
+/// Content-derived IDs for every content-addressable handle in a [`SmirJson`].
+pub struct StableIds {
+    ids: HashMap<(IdKind, usize), u64>,
+}
+
+impl StableIds {
+    /// Compute content-derived IDs for every handle reachable from `smir`.
+    ///
+    /// Runs in phase 3 but needs the compiler context for the type and def
+    /// path hashes; the result itself is plain data.
+    pub(super) fn build(tcx: TyCtxt<'_>, smir: &SmirJson) -> Self {
+        let recorder = Recorder::default();
+        smir.serialize(IdRemapSerializer::new(
+            &mut serde_json::Serializer::new(std::io::sink()),
+            &recorder,
+        ))
+        .expect("serializing to a sink cannot fail");
+
+        let mut builder = Builder {
+            tcx,
+            ids: HashMap::new(),
+            alloc_tys: smir
+                .allocs
+                .iter()
+                .map(|a| (a.alloc_id().to_index(), a.ty()))
+                .collect(),
+            spans: smir.spans.iter().cloned().collect(),
+            allocs_in_progress: HashSet::new(),
+        };
+        for (kind, raw) in recorder.seen.into_inner().order {
+            builder.id(kind, raw);
+        }
+        StableIds { ids: builder.ids }
+    }
+
+    pub fn ty(&self, ty: stable_mir::ty::Ty) -> u64 {
+        self.content_id(IdKind::Ty, ty.to_index())
+    }
+
+    pub fn alloc(&self, id: AllocId) -> u64 {
+        self.content_id(IdKind::AllocId, id.to_index())
+    }
+
+    pub fn span(&self, span: usize) -> u64 {
+        self.content_id(IdKind::Span, span)
+    }
+
+    fn content_id(&self, kind: IdKind, raw: usize) -> u64 {
+        *self.ids.get(&(kind, raw)).unwrap_or_else(|| {
+            panic!("No stable id recorded for {kind:?}({raw}); was StableIds built from this SmirJson?")
+        })
+    }
+
+    /// Serialize `value` (normally the [`SmirJson`] these IDs were built
+    /// from) with every interned handle replaced.
+    pub fn serialize<T: Serialize + ?Sized, S: Serializer>(
+        &self,
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self.emitter().serialize(value, serializer)
+    }
+
+    /// An emitter whose dense numbering carries over between `serialize`
+    /// calls, for output that is split over several files.
+    pub(super) fn emitter(&self) -> Emitter<'_> {
+        Emitter {
+            ids: self,
+            dense: RefCell::default(),
+        }
+    }
+}
+
+/// First pass: records which handles occur, in output order.
+#[derive(Default)]
+struct Recorder {
+    seen: RefCell<SeenIds>,
+}
+
+#[derive(Default)]
+struct SeenIds {
+    set: HashSet<(IdKind, usize)>,
+    order: Vec<(IdKind, usize)>,
+}
+
+impl IdMapper for Recorder {
+    fn map(&self, kind: IdKind, raw: usize) -> u64 {
+        if kind.is_content_derived() {
+            let mut seen = self.seen.borrow_mut();
+            if seen.set.insert((kind, raw)) {
+                seen.order.push((kind, raw));
+            }
+        }
+        raw as u64
+    }
+}
+
+/// Second pass: writes the precomputed content IDs, numbering the remaining
+/// handle kinds densely in the order they are written.
+pub(super) struct Emitter<'a> {
+    ids: &'a StableIds,
+    dense: RefCell<DenseIds>,
+}
+
+impl Emitter<'_> {
+    /// Serialize `value` with every interned handle replaced, continuing the
+    /// dense numbering of earlier calls.
+    pub(super) fn serialize<T: Serialize + ?Sized, S: Serializer>(
+        &self,
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.serialize(IdRemapSerializer::new(serializer, self))
+    }
+}
+
+#[derive(Default)]
+struct DenseIds {
+    ids: HashMap<(IdKind, usize), u64>,
+    next: HashMap<IdKind, u64>,
+}
+
+impl IdMapper for Emitter<'_> {
+    fn map(&self, kind: IdKind, raw: usize) -> u64 {
+        if kind.is_content_derived() {
+            return self.ids.content_id(kind, raw);
+        }
+        let DenseIds { ids, next } = &mut *self.dense.borrow_mut();
+        *ids.entry((kind, raw)).or_insert_with(|| {
+            let next = next.entry(kind).or_default();
+            *next += 1;
+            *next - 1
+        })
+    }
+}
+
+struct Builder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    ids: HashMap<(IdKind, usize), u64>,
+    alloc_tys: HashMap<usize, stable_mir::ty::Ty>,
+    spans: HashMap<usize, SourceData>,
+    allocs_in_progress: HashSet<usize>,
+}
+
+impl Builder<'_> {
+    fn id(&mut self, kind: IdKind, raw: usize) -> u64 {
+        if let Some(id) = self.ids.get(&(kind, raw)) {
+            return *id;
+        }
+        let id = match kind {
+            IdKind::Ty => {
+                let ty = stable_mir::ty::Ty::to_val(raw);
+                stable_hash(crate::compat::hashing::type_hash(self.tcx, ty))
+            }
+            IdKind::DefId => stable_hash(crate::compat::hashing::def_path_hash(
+                self.tcx,
+                stable_mir::DefId::to_val(raw),
+            )),
+            IdKind::AllocId => {
+                // Guard against cycles through provenance; any cycle must go
+                // through a `GlobalAlloc::Static`, whose ID does not recurse,
+                // so this is purely defensive.
+                if !self.allocs_in_progress.insert(raw) {
+                    return stable_hash(("alloc-cycle", raw));
+                }
+                let id = self.alloc_content_hash(raw);
+                self.allocs_in_progress.remove(&raw);
+                id
+            }
+            IdKind::Span => {
+                let data = self.spans.get(&raw).cloned().unwrap_or_else(|| {
+                    let span = stable_mir::ty::Span::to_val(raw);
+                    crate::compat::spans::resolve_span(self.tcx, &span)
+                });
+                stable_hash(data)
+            }
+            IdKind::TyConstId | IdKind::MirConstId | IdKind::InstanceDef => {
+                unreachable!("{kind:?} is numbered densely at emission time")
+            }
+        } & ID_MASK;
+        self.ids.insert((kind, raw), id);
+        id
+    }
+
+    fn ty_id(&mut self, ty: stable_mir::ty::Ty) -> u64 {
+        self.id(IdKind::Ty, ty.to_index())
+    }
+
+    fn def_id(&mut self, def: impl CrateDef) -> u64 {
+        self.id(IdKind::DefId, def.def_id().to_index())
+    }
+
+    fn alloc_content_hash(&mut self, raw: usize) -> u64 {
+        let ty = self.alloc_tys.get(&raw).copied();
+        match GlobalAlloc::from(AllocId::to_val(raw)) {
+            GlobalAlloc::Memory(alloc) => {
+                let ty_id = ty.map(|ty| self.ty_id(ty));
+                let provenance = alloc
+                    .provenance
+                    .ptrs
+                    .iter()
+                    .map(|(offset, prov)| (*offset, self.id(IdKind::AllocId, prov.0.to_index())))
+                    .collect::<Vec<_>>();
+                stable_hash((
+                    "Memory",
+                    ty_id,
+                    &alloc.bytes,
+                    alloc.align,
+                    alloc.mutability == stable_mir::mir::Mutability::Mut,
+                    provenance,
+                ))
+            }
+            GlobalAlloc::Static(def) => stable_hash(("Static", self.def_id(def))),
+            GlobalAlloc::VTable(vty, trait_ref) => {
+                let vty_id = self.ty_id(vty);
+                let trait_id = trait_ref.map(|binder| {
+                    let trait_ref = binder.value;
+                    let args = trait_ref
+                        .generic_args
+                        .0
+                        .iter()
+                        .map(|arg| match arg {
+                            GenericArgKind::Type(ty) => Some(self.ty_id(*ty)),
+                            GenericArgKind::Const(c) => c.eval_target_usize().ok(),
+                            GenericArgKind::Lifetime(_) => None,
+                        })
+                        .collect::<Vec<_>>();
+                    (self.def_id(trait_ref.def_id), args)
+                });
+                stable_hash(("VTable", vty_id, trait_id))
+            }
+            GlobalAlloc::Function(inst) => stable_hash(("Function", inst.mangled_name())),
+        }
+    }
+}
+
+// =============================================================================
+// Remapping serializer
+// =============================================================================
+
+/// Serializer adapter that replaces interned handles (see [`IdKind`]) via an
+/// [`IdMapper`] and forwards everything else to the wrapped serializer.
+struct IdRemapSerializer<'m, S, M> {
+    inner: S,
+    mapper: &'m M,
+}
+
+impl<'m, S, M> IdRemapSerializer<'m, S, M> {
+    fn new(inner: S, mapper: &'m M) -> Self {
+        IdRemapSerializer { inner, mapper }
+    }
+}
+
+/// A value to be serialized through an [`IdRemapSerializer`].
+struct Remapped<'a, 'm, T: ?Sized, M> {
+    value: &'a T,
+    mapper: &'m M,
+}
+
+impl<T: Serialize + ?Sized, M: IdMapper> Serialize for Remapped<'_, '_, T, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value
+            .serialize(IdRemapSerializer::new(serializer, self.mapper))
+    }
+}
+
+/// Compound-serializer adapter: wraps every element in [`Remapped`].
+struct RemapCompound<'m, C, M> {
+    inner: C,
+    mapper: &'m M,
+}
+
+impl<'m, C, M> RemapCompound<'m, C, M> {
+    fn wrap<'a, T: ?Sized>(&self, value: &'a T) -> Remapped<'a, 'm, T, M> {
+        Remapped {
+            value,
+            mapper: self.mapper,
+        }
+    }
+}
+
+macro_rules! forward_scalars {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'m, S: Serializer, M: IdMapper> Serializer for IdRemapSerializer<'m, S, M> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = RemapCompound<'m, S::SerializeSeq, M>;
+    type SerializeTuple = RemapCompound<'m, S::SerializeTuple, M>;
+    type SerializeTupleStruct = RemapCompound<'m, S::SerializeTupleStruct, M>;
+    type SerializeTupleVariant = RemapCompound<'m, S::SerializeTupleVariant, M>;
+    type SerializeMap = RemapCompound<'m, S::SerializeMap, M>;
+    type SerializeStruct = RemapCompound<'m, S::SerializeStruct, M>;
+    type SerializeStructVariant = RemapCompound<'m, S::SerializeStructVariant, M>;
+
+    forward_scalars!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    );
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        let mapper = self.mapper;
+        self.inner.serialize_some(&Remapped { value, mapper })
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        if let Some(kind) = IdKind::from_newtype_name(name) {
+            let raw = serde_json::to_value(value)
+                .ok()
+                .and_then(|v| v.as_u64())
+                .unwrap_or_else(|| panic!("{name} newtype does not wrap an index"));
+            let id = self.mapper.map(kind, raw as usize);
+            return self.inner.serialize_newtype_struct(name, &id);
+        }
+        let mapper = self.mapper;
+        self.inner
+            .serialize_newtype_struct(name, &Remapped { value, mapper })
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let mapper = self.mapper;
+        self.inner.serialize_newtype_variant(
+            name,
+            variant_index,
+            variant,
+            &Remapped { value, mapper },
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Ok(RemapCompound {
+            inner: self.inner.serialize_seq(len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Ok(RemapCompound {
+            inner: self.inner.serialize_tuple(len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        Ok(RemapCompound {
+            inner: self.inner.serialize_tuple_struct(name, len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        Ok(RemapCompound {
+            inner: self
+                .inner
+                .serialize_tuple_variant(name, variant_index, variant, len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        Ok(RemapCompound {
+            inner: self.inner.serialize_map(len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Ok(RemapCompound {
+            inner: self.inner.serialize_struct(name, len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        Ok(RemapCompound {
+            inner: self
+                .inner
+                .serialize_struct_variant(name, variant_index, variant, len)?,
+            mapper: self.mapper,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<C: SerializeSeq, M: IdMapper> SerializeSeq for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTuple, M: IdMapper> SerializeTuple for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleStruct, M: IdMapper> SerializeTupleStruct for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleVariant, M: IdMapper> SerializeTupleVariant for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeMap, M: IdMapper> SerializeMap for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        let key = self.wrap(key);
+        self.inner.serialize_key(&key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStruct, M: IdMapper> SerializeStruct for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStructVariant, M: IdMapper> SerializeStructVariant for RemapCompound<'_, C, M> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
@@ -4,9 +4,16 @@
 //! type trees, recording each relevant type along with its `TyKind` and
 //! `LayoutShape`. These collected types are later transformed into
 //! [`TypeMetadata`](super::schema::TypeMetadata) entries in the final output.
-//! Note that some special kinds (function definitions/pointers and coroutine
-//! witnesses) are traversed only to gather the types they reference and are
-//! not themselves stored as entries in the type map.
+//! Function definitions and pointers are traversed through their resolved
+//! instance or ABI, and stored with their signature (which becomes a
+//! `FnSigType` entry). Coroutine witnesses are neither traversed nor stored:
+//! closures and coroutines are traversed through their captured variables
+//! and saved locals rather than their generic arguments, which hold the
+//! coroutine witness.
+//!
+//! Types from polymorphic bodies that mention generic parameters have no
+//! layout and cannot be resolved to instances; they are recorded without a
+//! layout, and function types are traversed through their signature.
 
 use crate::compat::middle::ty::TyCtxt;
 use crate::compat::stable_mir;
@@ -15,6 +22,7 @@ use std::collections::{HashMap, HashSet};
 use std::ops::ControlFlow;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 
+use stable_mir::abi::FnAbi;
 use stable_mir::mir::mono::Instance;
 use stable_mir::ty::{RigidTy, TyKind};
 use stable_mir::visitor::{Visitable, Visitor};
@@ -93,9 +101,52 @@ impl TyCollector<'_> {
         }
     }
 
+    /// Visit a type that mentions generic parameters.
+    fn visit_polymorphic(&mut self, ty: &stable_mir::ty::Ty) -> ControlFlow<()> {
+        let kind = ty.kind();
+        match kind {
+            TyKind::RigidTy(RigidTy::FnDef(..)) | TyKind::RigidTy(RigidTy::FnPtr(_)) => {
+                self.resolved.insert(*ty);
+                let sig = kind.fn_sig().unwrap();
+                sig.value.inputs_and_output.super_visit(self)?;
+                ty.super_visit(self)
+            }
+            TyKind::RigidTy(RigidTy::Adt(adt_def, ref args)) => {
+                ty.super_visit(self)?;
+                self.types.insert(*ty, (kind.clone(), None));
+                let fields = adt_def
+                    .variants()
+                    .iter()
+                    .flat_map(|v| v.fields())
+                    .map(|f| crate::compat::types::field_ty_with_args(self.tcx, &f, args))
+                    .collect::<Vec<_>>();
+                fields.super_visit(self)
+            }
+            _ => {
+                ty.super_visit(self)?;
+                self.types.insert(*ty, (kind, None));
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    /// Record a function or closure type, after the types in its signature.
+    fn visit_fn_sig(&mut self, ty: &stable_mir::ty::Ty) -> ControlFlow<()> {
+        if let Some(sig) = crate::compat::types::fn_sig(self.tcx, *ty) {
+            sig.inputs_and_output.super_visit(self)?;
+        }
+        let maybe_layout_shape = self.layout_shape_or_record(ty);
+        self.types.insert(*ty, (ty.kind(), maybe_layout_shape));
+        ControlFlow::Continue(())
+    }
+
     #[inline(always)]
     fn visit_instance(&mut self, instance: Instance) -> ControlFlow<<Self as Visitor>::Break> {
-        let fn_abi = instance.fn_abi().unwrap();
+        self.visit_fn_abi(&instance.fn_abi().unwrap())
+    }
+
+    /// Visit the argument and return types of a function ABI.
+    fn visit_fn_abi(&mut self, fn_abi: &FnAbi) -> ControlFlow<()> {
         let mut inputs_outputs: Vec<stable_mir::ty::Ty> =
             fn_abi.args.iter().map(|arg_abi| arg_abi.ty).collect();
         inputs_outputs.push(fn_abi.ret.ty);
@@ -110,6 +161,9 @@ impl Visitor for TyCollector<'_> {
         if self.types.contains_key(ty) || self.resolved.contains(ty) {
             return ControlFlow::Continue(());
         }
+        if crate::compat::types::has_param(self.tcx, *ty) {
+            return self.visit_polymorphic(ty);
+        }
 
         match ty.kind() {
             TyKind::RigidTy(RigidTy::Closure(def, ref args)) => {
@@ -119,10 +173,19 @@ impl Visitor for TyCollector<'_> {
                 let control = self.visit_instance(instance);
                 // Mirror other branches: record closure Ty only when traversal succeeds.
                 if matches!(control, ControlFlow::Continue(_)) {
-                    let maybe_layout_shape = self.layout_shape_or_record(ty);
-                    self.types.insert(*ty, (ty.kind(), maybe_layout_shape));
+                    self.visit_fn_sig(ty)?;
+                    crate::compat::types::upvar_tys(self.tcx, *ty).super_visit(self)
+                } else {
+                    control
                 }
-                control
+            }
+            // Recorded before its saved locals, which may refer back to it
+            TyKind::RigidTy(RigidTy::Coroutine(..)) => {
+                let maybe_layout_shape = self.layout_shape_or_record(ty);
+                self.types.insert(*ty, (ty.kind(), maybe_layout_shape));
+                let (saved_locals, _) = crate::compat::types::coroutine_saved_locals(self.tcx, *ty);
+                crate::compat::types::upvar_tys(self.tcx, *ty).super_visit(self)?;
+                saved_locals.super_visit(self)
             }
             // Break on CoroutineWitnesses, because they aren't expected when getting the layout
             TyKind::RigidTy(RigidTy::CoroutineWitness(..)) => {
@@ -132,7 +195,8 @@ impl Visitor for TyCollector<'_> {
             TyKind::RigidTy(RigidTy::FnDef(def, ref args)) => {
                 self.resolved.insert(*ty);
                 let instance = Instance::resolve(def, args).unwrap();
-                self.visit_instance(instance)
+                self.visit_instance(instance)?;
+                self.visit_fn_sig(ty)
             }
             TyKind::RigidTy(RigidTy::FnPtr(binder_stable)) => {
                 self.resolved.insert(*ty);
@@ -140,7 +204,8 @@ impl Visitor for TyCollector<'_> {
                 let mut inputs_outputs: Vec<stable_mir::ty::Ty> =
                     fn_abi.args.iter().map(|arg_abi| arg_abi.ty).collect();
                 inputs_outputs.push(fn_abi.ret.ty);
-                inputs_outputs.super_visit(self)
+                inputs_outputs.super_visit(self)?;
+                self.visit_fn_sig(ty)
             }
             // The visitor won't collect field types for ADTs, therefore doing it explicitly
             TyKind::RigidTy(RigidTy::Adt(adt_def, args)) => {
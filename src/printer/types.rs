@@ -8,10 +8,15 @@
 use crate::compat::middle::ty::TyCtxt;
 use crate::compat::stable_mir;
 
-use stable_mir::abi::LayoutShape;
-use stable_mir::ty::TyKind;
+use stable_mir::abi::{FieldsShape, LayoutShape, Scalar, TagEncoding, ValueAbi, VariantsShape};
+use stable_mir::target::MachineInfo;
+use stable_mir::ty::{AdtDef, IndexedVal, TyKind, VariantDef};
 
-use super::schema::TypeMetadata;
+use crate::compat::types::{
+    adt_repr, coroutine_saved_locals, field_ty_with_args, fn_sig, upvar_tys, ReprOptions,
+};
+
+use super::schema::{AdtRepr, EnumLayout, EnumTag, TypeMetadata, VariantLayout};
 
 pub(super) fn mk_type_metadata(
     tcx: TyCtxt<'_>,
@@ -29,16 +34,21 @@ pub(super) fn mk_type_metadata(
         // this requires access to the internals and is not provided as an interface function at the moment
         T(Adt(adt_def, args)) if t.is_enum() => {
             let discriminants = crate::compat::types::adt_discriminants(tcx, adt_def);
-            let fields = adt_def
-                .variants()
+            let variants = adt_def.variants();
+            let fields = variants
                 .iter()
                 .map(|v| {
                     v.fields()
                         .iter()
-                        .map(|f| f.ty_with_args(&args))
+                        .map(|f| field_ty_with_args(tcx, f, &args))
                         .collect::<Vec<stable_mir::ty::Ty>>()
                 })
                 .collect();
+            let variant_names = variants.iter().map(|v| v.name()).collect();
+            let field_names = variants.iter().map(field_names).collect();
+            let enum_layout = layout
+                .as_ref()
+                .map(|layout| mk_enum_layout(layout, &discriminants));
             Some((
                 k,
                 EnumType {
@@ -46,18 +56,24 @@ pub(super) fn mk_type_metadata(
                     adt_def,
                     discriminants,
                     fields,
+                    variant_names,
+                    field_names,
+                    repr: mk_adt_repr(tcx, adt_def),
+                    generic_args: args,
                     layout,
+                    enum_layout,
                 },
             ))
         }
         T(Adt(adt_def, args)) if t.is_struct() => {
-            let fields = adt_def
+            let variant = adt_def
                 .variants()
                 .pop() // NB struct, there should be a single variant
-                .unwrap()
+                .unwrap();
+            let fields = variant
                 .fields()
                 .iter()
-                .map(|f| f.ty_with_args(&args))
+                .map(|f| field_ty_with_args(tcx, f, &args))
                 .collect();
             Some((
                 k,
@@ -65,18 +81,22 @@ pub(super) fn mk_type_metadata(
                     name,
                     adt_def,
                     fields,
+                    field_names: field_names(&variant),
+                    repr: mk_adt_repr(tcx, adt_def),
+                    generic_args: args,
                     layout,
                 },
             ))
         }
         T(Adt(adt_def, args)) if t.is_union() => {
-            let fields = adt_def
+            let variant = adt_def
                 .variants()
                 .pop() // TODO: Check union has single variant
-                .unwrap()
+                .unwrap();
+            let fields = variant
                 .fields()
                 .iter()
-                .map(|f| f.ty_with_args(&args))
+                .map(|f| field_ty_with_args(tcx, f, &args))
                 .collect();
             Some((
                 k,
@@ -84,6 +104,9 @@ pub(super) fn mk_type_metadata(
                     name,
                     adt_def,
                     fields,
+                    field_names: field_names(&variant),
+                    repr: mk_adt_repr(tcx, adt_def),
+                    generic_args: args,
                     layout,
                 },
             ))
@@ -135,11 +158,60 @@ pub(super) fn mk_type_metadata(
         )),
         // for tuples the element types are provided
         T(Tuple(types)) => Some((k, TupleType { types, layout })),
-        // opaque function types (fun ptrs, closures, FnDef) are only provided to avoid dangling ty references
-        T(FnDef(_, _)) | T(FnPtr(_)) | T(Closure(_, _)) => Some((k, FunType(name))),
+        // function definitions and pointers record their signature
+        T(FnDef(_, _)) | T(FnPtr(_)) => Some((
+            k,
+            FnSigType {
+                name,
+                sig: fn_sig(tcx, k)?.into(),
+                layout,
+            },
+        )),
+        // closures and coroutines record their captures (and saved locals)
+        T(Closure(closure_def, _)) => Some((
+            k,
+            ClosureType {
+                name,
+                closure_def,
+                sig: fn_sig(tcx, k)?.into(),
+                upvar_tys: upvar_tys(tcx, k),
+                layout,
+            },
+        )),
+        T(Coroutine(coroutine_def, _, _)) => {
+            let (saved_locals, variants) = coroutine_saved_locals(tcx, k);
+            Some((
+                k,
+                CoroutineType {
+                    name,
+                    coroutine_def,
+                    upvar_tys: upvar_tys(tcx, k),
+                    saved_locals,
+                    variants,
+                    layout,
+                },
+            ))
+        }
+        T(Pat(base, pattern)) => Some((
+            k,
+            PatternType {
+                base,
+                pattern,
+                layout,
+            },
+        )),
+        T(Foreign(foreign_def)) => Some((
+            k,
+            ForeignType {
+                name,
+                foreign_def,
+                layout,
+            },
+        )),
         // other types are not provided either
         T(Dynamic(_, _, _)) => Some((k, DynType { name, layout })),
-        T(Foreign(_)) | T(Pat(_, _)) | T(Coroutine(_, _, _)) | T(CoroutineWitness(_, _)) => {
+        // witnesses only occur in a coroutine's generic arguments
+        T(CoroutineWitness(_, _)) => {
             debug_log_println!(
                 "\nDEBUG: Skipping unsupported ty {}: {:?}",
                 k.to_index(),
@@ -148,7 +220,10 @@ pub(super) fn mk_type_metadata(
             None
         }
         T(Never) => Some((k, VoidType)),
-        TyKind::Alias(_, _) | TyKind::Param(_) | TyKind::Bound(_, _) => {
+        // only found in polymorphic bodies
+        TyKind::Param(param) => Some((k, ParamType(param))),
+        TyKind::Alias(kind, alias) => Some((k, AliasType { name, kind, alias })),
+        TyKind::Bound(_, _) => {
             debug_log_println!("\nSkipping undesired ty {}: {:?}", k.to_index(), k.kind());
             None
         }
@@ -159,3 +234,103 @@ pub(super) fn mk_type_metadata(
         }
     }
 }
+
+fn mk_adt_repr(tcx: TyCtxt<'_>, adt_def: AdtDef) -> AdtRepr {
+    let ReprOptions {
+        c,
+        transparent,
+        simd,
+        int,
+        packed,
+        align,
+    } = adt_repr(tcx, adt_def);
+    AdtRepr {
+        c,
+        transparent,
+        simd,
+        int,
+        packed,
+        align,
+    }
+}
+
+fn field_names(variant: &VariantDef) -> Vec<String> {
+    variant.fields().into_iter().map(|f| f.name).collect()
+}
+
+fn field_offsets(fields: &FieldsShape) -> Vec<usize> {
+    match fields {
+        FieldsShape::Arbitrary { offsets } => offsets.iter().map(|o| o.bytes()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Decode the tag and variant layouts of an enum from its layout, given the
+/// discriminant of each variant.
+pub(super) fn mk_enum_layout(layout: &LayoutShape, discriminants: &[u128]) -> EnumLayout {
+    match &layout.variants {
+        VariantsShape::Single { index } => {
+            // at most one variant is inhabited, laid out as the enum itself
+            let variants = (0..discriminants.len())
+                .map(|i| {
+                    (i == index.to_index()).then(|| VariantLayout {
+                        field_offsets: field_offsets(&layout.fields),
+                        tag_value: None,
+                        uninhabited: layout.abi == ValueAbi::Uninhabited,
+                    })
+                })
+                .collect();
+            EnumLayout {
+                tag: None,
+                variants,
+            }
+        }
+        VariantsShape::Multiple {
+            tag,
+            tag_encoding,
+            tag_field,
+            variants,
+        } => {
+            let (primitive, valid_range) = match *tag {
+                Scalar::Initialized { value, valid_range } => (value, valid_range),
+                Scalar::Union { .. } => unreachable!("enum tags are initialized scalars"),
+            };
+            let size = primitive.size(&MachineInfo::target()).bytes();
+            let mask = u128::MAX >> (128 - 8 * size);
+            let tag_value = |i: usize| match tag_encoding {
+                TagEncoding::Direct => Some(discriminants[i] & mask),
+                TagEncoding::Niche {
+                    niche_variants,
+                    niche_start,
+                    ..
+                } => {
+                    let (start, end) = (niche_variants.start(), niche_variants.end());
+                    (start.to_index()..=end.to_index())
+                        .contains(&i)
+                        .then(|| ((i - start.to_index()) as u128).wrapping_add(*niche_start) & mask)
+                }
+            };
+            let variants = variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| {
+                    Some(VariantLayout {
+                        field_offsets: field_offsets(&variant.fields),
+                        tag_value: tag_value(i),
+                        uninhabited: variant.abi == ValueAbi::Uninhabited,
+                    })
+                })
+                .collect();
+            EnumLayout {
+                tag: Some(EnumTag {
+                    offset: field_offsets(&layout.fields)[*tag_field],
+                    size,
+                    primitive,
+                    valid_range,
+                    encoding: tag_encoding.clone(),
+                }),
+                variants,
+            }
+        }
+    }
+}
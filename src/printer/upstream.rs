@@ -0,0 +1,62 @@
+//! Including the bodies of upstream functions.
+//!
+//! The mono items of a crate only cover the code generated in it: a call
+//! into a non-generic function of a dependency shows up in `functions` as a
+//! `NormalSym` without a body. With an [`UpstreamBodies`] selection
+//! (`--smir-upstream`, `--smir-upstream-depth`, `--smir-upstream-crate`),
+//! such callees are added to `items` if their crate encoded MIR for them
+//! (optimized MIR, or the const-eval MIR of a `const fn`), and so are their
+//! own upstream callees, transitively:
+//!
+//! - **Depth**: callees of the local crate's items are at depth 1, their
+//!   callees at depth 2, and so on; only callees up to `depth` are added
+//!   (all of them if unset).
+//! - **Crates**: only callees defined in one of `crates` are added (from any
+//!   crate if empty).
+//!
+//! Callees are found the way the reachability filter finds items (calls,
+//! drops, function pointers, vtables). They are added a depth level at a
+//! time, so the result does not depend on the order in which bodies are
+//! analyzed. Functions monomorphized in the local crate are never added
+//! again, even if the item filter dropped them, and the path globs apply to
+//! callees as well. Added items carry their crate's name in
+//! `external_crate`.
+
+use crate::compat::serde;
+use crate::compat::stable_mir;
+
+use serde::Serialize;
+use stable_mir::mir::mono::{Instance, InstanceKind, MonoItem};
+use stable_mir::CrateDef;
+
+/// Which upstream bodies to include; see the [module documentation](self).
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UpstreamBodies {
+    /// Greatest depth of an added callee; unlimited if `None`.
+    pub depth: Option<usize>,
+    /// Names of the crates whose functions may be added; any if empty.
+    pub crates: Vec<String>,
+}
+
+impl UpstreamBodies {
+    /// Whether callees at `depth` may be added.
+    pub(super) fn admits_depth(&self, depth: usize) -> bool {
+        self.depth.is_none_or(|max| depth <= max)
+    }
+
+    /// The instance and crate name of `item`, if it is a function of an
+    /// admitted upstream crate.
+    pub(super) fn upstream_fn(&self, item: &MonoItem) -> Option<(Instance, String)> {
+        let MonoItem::Fn(inst) = item else {
+            return None;
+        };
+        if inst.kind != InstanceKind::Item {
+            return None;
+        }
+        let krate = inst.def.krate();
+        if krate.is_local || !(self.crates.is_empty() || self.crates.contains(&krate.name)) {
+            return None;
+        }
+        Some((*inst, krate.name))
+    }
+}
@@ -0,0 +1,123 @@
+//! Struct, enum and union types carry their field and variant names, their
+//! `#[repr]` options and the generic arguments they are instantiated with.
+
+mod common;
+
+use common::run_resource;
+use stable_mir_json_reader::ty::{GenericArgKind, RigidTy, UintTy};
+use stable_mir_json_reader::{AdtRepr, SmirJson, TypeMetadata};
+
+fn find<'a>(smir: &'a SmirJson, type_name: &str) -> &'a TypeMetadata {
+    smir.types
+        .iter()
+        .map(|(_, metadata)| metadata)
+        .find(|metadata| match metadata {
+            TypeMetadata::EnumType { name, .. }
+            | TypeMetadata::StructType { name, .. }
+            | TypeMetadata::UnionType { name, .. } => name == type_name,
+            _ => false,
+        })
+        .unwrap_or_else(|| panic!("no type {type_name}"))
+}
+
+fn repr(smir: &SmirJson, type_name: &str) -> AdtRepr {
+    match find(smir, type_name) {
+        TypeMetadata::EnumType { repr, .. }
+        | TypeMetadata::StructType { repr, .. }
+        | TypeMetadata::UnionType { repr, .. } => repr.clone(),
+        _ => unreachable!(),
+    }
+}
+
+const DEFAULT_REPR: AdtRepr = AdtRepr {
+    c: false,
+    transparent: false,
+    simd: false,
+    int: None,
+    packed: None,
+    align: None,
+};
+
+#[test]
+fn adt_names_reprs_and_generic_args() {
+    let smir = run_resource("adt_metadata", &[], &[]);
+
+    let TypeMetadata::StructType {
+        field_names,
+        generic_args,
+        fields,
+        ..
+    } = find(&smir, "Pair<u16>")
+    else {
+        unreachable!()
+    };
+    assert_eq!(field_names, &["first", "second"]);
+    let [GenericArgKind::Type(arg)] = generic_args.0.as_slice() else {
+        panic!("unexpected generic args {generic_args:?}");
+    };
+    assert_eq!(arg, &fields[0]);
+    assert!(smir.types.contains(&(
+        *arg,
+        TypeMetadata::PrimitiveType(RigidTy::Uint(UintTy::U16))
+    )));
+
+    let TypeMetadata::EnumType {
+        variant_names,
+        field_names,
+        generic_args,
+        ..
+    } = find(&smir, "Shape")
+    else {
+        unreachable!()
+    };
+    assert_eq!(variant_names, &["Point", "Circle", "Rect"]);
+    assert_eq!(
+        field_names,
+        &[
+            vec![],
+            vec!["radius".to_string()],
+            vec!["0".into(), "1".into()]
+        ]
+    );
+    assert!(generic_args.0.is_empty());
+
+    let TypeMetadata::UnionType { field_names, .. } = find(&smir, "Bits") else {
+        unreachable!()
+    };
+    assert_eq!(field_names, &["int", "float"]);
+
+    let c = AdtRepr {
+        c: true,
+        ..DEFAULT_REPR
+    };
+    assert_eq!(repr(&smir, "Pair<u16>"), c);
+    assert_eq!(
+        repr(&smir, "Packed"),
+        AdtRepr {
+            packed: Some(2),
+            ..c
+        }
+    );
+    assert_eq!(
+        repr(&smir, "Aligned"),
+        AdtRepr {
+            align: Some(16),
+            ..DEFAULT_REPR
+        }
+    );
+    assert_eq!(
+        repr(&smir, "Shape"),
+        AdtRepr {
+            int: Some(RigidTy::Uint(UintTy::U8)),
+            ..DEFAULT_REPR
+        }
+    );
+    assert_eq!(
+        repr(&smir, "Meters"),
+        AdtRepr {
+            transparent: true,
+            ..DEFAULT_REPR
+        }
+    );
+    assert_eq!(repr(&smir, "Bits"), DEFAULT_REPR);
+}
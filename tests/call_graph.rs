@@ -0,0 +1,86 @@
+//! The `calls` edges link each item to the functions its body calls.
+
+mod common;
+use common::{get_resource_path, out_dir, run_driver_with_args, run_resource};
+use stable_mir_json_reader::{CallEdge, CallKind, FnSymType, MonoItemKind, SmirJson};
+
+const PROGRAM: [&str; 3] = ["tests", "resources", "call_graph.rs"];
+
+/// The symbol name of the function item with the given (short) name.
+fn symbol<'a>(smir: &'a SmirJson, fn_name: &str) -> &'a str {
+    smir.items
+        .iter()
+        .find(|item| {
+            matches!(&item.mono_item_kind, MonoItemKind::MonoItemFn { name, .. }
+                if name.rsplit("::").next() == Some(fn_name))
+        })
+        .map(|item| item.symbol_name.as_str())
+        .unwrap_or_else(|| panic!("no item {fn_name}"))
+}
+
+fn calls_from<'a>(smir: &'a SmirJson, caller: &str) -> Vec<&'a CallEdge> {
+    smir.calls.iter().filter(|c| c.caller == caller).collect()
+}
+
+fn calls(edge: &CallEdge, callee: &str) -> bool {
+    edge.callee == FnSymType::NormalSym(callee.to_string())
+}
+
+#[test]
+fn calls_are_edges_of_their_kind() {
+    let smir = run_resource("call_graph", &[], &[]);
+    let main = symbol(&smir, "main");
+    let fact = symbol(&smir, "fact");
+    let twice = symbol(&smir, "twice");
+    let total = symbol(&smir, "total");
+
+    let main_calls = calls_from(&smir, main);
+    for callee in [twice, total] {
+        assert!(main_calls
+            .iter()
+            .any(|c| calls(c, callee) && c.kind == CallKind::Direct));
+    }
+    // `fact` is passed as a function pointer
+    assert!(main_calls
+        .iter()
+        .any(|c| calls(c, fact) && c.kind == CallKind::FnPtr));
+    assert!(main_calls.iter().any(|c| c.kind == CallKind::DropGlue));
+
+    // recursion is a self-loop, and every call site spans a source location
+    let fact_calls = calls_from(&smir, fact);
+    assert!(fact_calls
+        .iter()
+        .any(|c| calls(c, fact) && c.kind == CallKind::Direct));
+    for call in &smir.calls {
+        assert!(smir.spans.iter().any(|(span, _)| *span == call.span));
+    }
+
+    // calls through a function pointer have no edge of their own
+    assert!(calls_from(&smir, twice).is_empty());
+
+    // a call through `dyn Count` selects the first method slot
+    assert!(calls_from(&smir, total)
+        .iter()
+        .any(|c| c.kind == CallKind::Virtual { idx: 3 }));
+}
+
+#[test]
+fn call_graph_renders_as_dot_and_graphml() {
+    let program = get_resource_path(PROGRAM.to_vec());
+    let dir = out_dir("call-graph", "formats");
+    run_driver_with_args(
+        program.as_ref(),
+        &dir,
+        &[],
+        &["--smir-format=calls-dot,calls-graphml"],
+    );
+    let dot = std::fs::read_to_string(dir.join("call_graph.smir.calls.dot")).unwrap();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("fn ptr"));
+    assert!(dot.contains("virtual #3"));
+
+    let graphml = std::fs::read_to_string(dir.join("call_graph.smir.calls.graphml")).unwrap();
+    assert!(graphml.contains("<graphml"));
+    assert!(graphml.contains("<data key=\"kind\">drop</data>"));
+    assert!(graphml.contains("<data key=\"name\">fact</data>"));
+}
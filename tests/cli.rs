@@ -0,0 +1,160 @@
+//! Command-line options of the `stable_mir_json` driver.
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use common::{get_resource_path, out_dir};
+
+fn program() -> PathBuf {
+    get_resource_path(vec!["tests", "integration", "programs", "binop.rs"]).into()
+}
+
+fn driver(args: &[&str]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_stable_mir_json"));
+    for var in ["LINK_ITEMS", "LINK_INST", "DEBUG", "STABLE_IDS"] {
+        cmd.env_remove(var);
+    }
+    cmd.arg("-Zno-codegen").args(args).arg(program());
+    cmd.output().expect("failed to run stable_mir_json")
+}
+
+fn assert_rejected(args: &[&str], message: &str) {
+    let output = driver(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{args:?} was accepted");
+    assert!(
+        stderr.contains(message),
+        "{args:?}: expected `{message}` in:\n{stderr}"
+    );
+}
+
+#[test]
+fn flags_and_output_path() {
+    let out = out_dir("cli", "flags").join("custom-name.json");
+    let output = driver(&[
+        "--smir-link-items",
+        &format!("--smir-out={}", out.display()),
+        "--smir-link-inst",
+    ]);
+    assert!(output.status.success());
+    let smir = stable_mir_json_reader::load(&out).unwrap();
+    let flags = smir.header.flags;
+    assert!(flags.link_items && flags.link_inst);
+    assert!(!flags.debug && !flags.stable_ids);
+    assert!(smir
+        .functions
+        .iter()
+        .all(|(key, ..)| key.instance_kind.is_some()));
+}
+
+#[test]
+fn output_to_stdout() {
+    let output = driver(&["--smir-out", "-"]);
+    assert!(output.status.success());
+    let smir = stable_mir_json_reader::from_str(std::str::from_utf8(&output.stdout).unwrap())
+        .expect("stdout is not a smir.json document");
+    assert_eq!(smir.header.flags, Default::default());
+}
+
+#[test]
+fn several_formats() {
+    let dir = out_dir("cli", "formats");
+    let output = driver(&[
+        "--smir-format=json,dot,d2",
+        "--out-dir",
+        dir.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    for ext in ["smir.json", "smir.dot", "smir.d2"] {
+        assert!(dir.join("binop").with_extension(ext).exists(), "no {ext}");
+    }
+}
+
+#[test]
+fn invalid_options() {
+    assert_rejected(&["--smir-format=xml"], "unknown output format `xml`");
+    assert_rejected(&["--smir-bogus"], "unknown option `--smir-bogus`");
+    assert_rejected(
+        &["--smir-debug=yes"],
+        "`--smir-debug` does not take a value",
+    );
+    assert_rejected(
+        &["--smir-format=json,dot", "--smir-out=x"],
+        "`--smir-out` can only be used with a single output format",
+    );
+    assert_rejected(
+        &["--smir-upstream-depth=deep"],
+        "invalid value `deep` for `--smir-upstream-depth`",
+    );
+    assert_rejected(
+        &["--smir-upstream-depth=1", "--smir-upstream-depth=2"],
+        "`--smir-upstream-depth` given more than once",
+    );
+    assert_rejected(
+        &["--smir-format=split", "--smir-out=-"],
+        "`split` output is a directory and cannot go to stdout",
+    );
+}
+
+#[test]
+fn split_output() {
+    let dir = out_dir("cli", "split").join("binop.smir");
+    let _ = std::fs::remove_dir_all(&dir);
+    let output = driver(&[
+        "--smir-format=split",
+        &format!("--smir-out={}", dir.display()),
+    ]);
+    assert!(output.status.success());
+    let index = stable_mir_json_reader::load_split_index(&dir).unwrap();
+    assert!(!index.items.is_empty());
+    for entry in &index.items {
+        let item = stable_mir_json_reader::load_split_item(&dir, entry).unwrap();
+        assert_eq!(item.symbol_name, entry.symbol_name);
+    }
+    let files = std::fs::read_dir(dir.join("items")).unwrap().count();
+    assert_eq!(files, index.items.len());
+}
+
+#[test]
+fn split_output_keeps_foreign_items_dir() {
+    let dir = out_dir("cli", "split-foreign");
+    let _ = std::fs::remove_dir_all(dir.join("items"));
+    let foreign = dir.join("items").join("notes.txt");
+    std::fs::create_dir_all(foreign.parent().unwrap()).unwrap();
+    std::fs::write(&foreign, "not ours").unwrap();
+    let output = driver(&[
+        "--smir-format=split",
+        &format!("--smir-out={}", dir.display()),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not from a split output"));
+    assert_eq!(std::fs::read_to_string(&foreign).unwrap(), "not ours");
+
+    // an earlier split output is replaced
+    let dir = out_dir("cli", "split-again").join("binop.smir");
+    let _ = std::fs::remove_dir_all(&dir);
+    for _ in 0..2 {
+        let output = driver(&[
+            "--smir-format=split",
+            &format!("--smir-out={}", dir.display()),
+        ]);
+        assert!(output.status.success());
+    }
+}
+
+#[test]
+fn compile_errors_fail() {
+    let broken = out_dir("cli", "broken").join("broken.rs");
+    std::fs::write(&broken, "fn main() { undefined(); }\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_stable_mir_json"))
+        .arg("-Zno-codegen")
+        .arg("--out-dir")
+        .arg(broken.parent().unwrap())
+        .arg(&broken)
+        .output()
+        .expect("failed to run stable_mir_json");
+    assert!(!output.status.success());
+    assert!(!broken.with_extension("smir.json").exists());
+}
@@ -0,0 +1,82 @@
+//! Closures, coroutines, pattern types and extern types get their own
+//! `types` entries, with captures and saved locals, so that values of these
+//! types can be interpreted.
+
+mod common;
+
+use common::{run_resource, uint_ty};
+use stable_mir_json_reader::ty::UintTy;
+use stable_mir_json_reader::{SmirJson, TypeMetadata};
+
+fn find(smir: &SmirJson, pred: impl Fn(&TypeMetadata) -> bool) -> &TypeMetadata {
+    &smir
+        .types
+        .iter()
+        .find(|(_, metadata)| pred(metadata))
+        .expect("no such type")
+        .1
+}
+
+#[test]
+fn closure_and_coroutine_metadata() {
+    let smir = run_resource("closures_coroutines", &[], &["--edition=2021"]);
+    let (u8_ty, u32_ty) = (uint_ty(&smir, UintTy::U8), uint_ty(&smir, UintTy::U32));
+
+    let TypeMetadata::ClosureType {
+        upvar_tys, layout, ..
+    } = find(
+        &smir,
+        |m| matches!(m, TypeMetadata::ClosureType { name, .. } if name.contains("closures_coroutines.rs")),
+    )
+    else {
+        unreachable!()
+    };
+    let mut captured = upvar_tys.clone();
+    captured.sort();
+    let mut expected = vec![u8_ty, u32_ty];
+    expected.sort();
+    assert_eq!(captured, expected);
+    assert!(layout.is_some());
+
+    let TypeMetadata::CoroutineType {
+        upvar_tys,
+        saved_locals,
+        variants,
+        layout,
+        ..
+    } = find(
+        &smir,
+        |m| matches!(m, TypeMetadata::CoroutineType { name, .. } if name.contains("compute")),
+    )
+    else {
+        unreachable!()
+    };
+    assert_eq!(upvar_tys, &[u32_ty]);
+    assert!(layout.is_some());
+    // Unresumed, returned and poisoned, then one state per `.await`.
+    assert_eq!(variants.len(), 5);
+    assert!(variants[..3].iter().all(Vec::is_empty));
+    assert!(variants[3..].iter().all(|locals| !locals.is_empty()));
+    assert!(variants.iter().flatten().all(|&i| i < saved_locals.len()));
+    // The awaited `add` futures are saved, and have entries themselves.
+    for ty in saved_locals {
+        assert!(smir.types.iter().any(|(t, _)| t == ty));
+    }
+}
+
+#[test]
+fn pattern_and_foreign_types() {
+    let smir = run_resource("closures_coroutines", &[], &["--edition=2021"]);
+    let u32_ty = uint_ty(&smir, UintTy::U32);
+    let TypeMetadata::PatternType { base, layout, .. } =
+        find(&smir, |m| matches!(m, TypeMetadata::PatternType { .. }))
+    else {
+        unreachable!()
+    };
+    assert_eq!(*base, u32_ty);
+    assert!(layout.is_some());
+    assert!(matches!(
+        find(&smir, |m| matches!(m, TypeMetadata::ForeignType { .. })),
+        TypeMetadata::ForeignType { name, .. } if name == "Opaque"
+    ));
+}
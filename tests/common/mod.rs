@@ -1,4 +1,11 @@
-use std::path::PathBuf;
+// Not every test crate uses every helper.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use stable_mir_json_reader::ty::{RigidTy, Ty, UintTy};
+use stable_mir_json_reader::{SmirJson, TypeMetadata};
 
 /// Given a vector of components, builds, validates, and returns a test resource path
 pub fn get_resource_path(components: Vec<&str>) -> String {
@@ -14,8 +21,102 @@ pub fn get_resource_path(components: Vec<&str>) -> String {
         );
     }
 
-    return path
-        .to_str()
+    path.to_str()
         .expect("test path was not a valid string")
-        .into();
+        .into()
+}
+
+/// Every `*.rs` program under `tests/integration/programs`, sorted.
+pub fn test_programs() -> Vec<PathBuf> {
+    let dir = get_resource_path(vec!["tests", "integration", "programs"]);
+    let mut programs = std::fs::read_dir(dir)
+        .expect("failed to read test program directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert!(!programs.is_empty(), "no test programs found");
+    programs
+}
+
+/// A directory under `CARGO_TARGET_TMPDIR` for the output of `test` in
+/// `suite`, created if missing.
+pub fn out_dir(suite: &str, test: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(suite)
+        .join(test);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run the driver on `program` with each variable in `env` set to `1`,
+/// returning the path of the `*.smir.json` it wrote into `out_dir`.
+pub fn run_driver(program: &Path, out_dir: &Path, env: &[&str]) -> PathBuf {
+    run_driver_with_args(program, out_dir, env, &[])
+}
+
+/// Like [`run_driver`], passing `args` to the driver before the program.
+pub fn run_driver_with_args(
+    program: &Path,
+    out_dir: &Path,
+    env: &[&str],
+    args: &[&str],
+) -> PathBuf {
+    std::fs::create_dir_all(out_dir).unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_stable_mir_json"));
+    cmd.args(args)
+        .arg("-Zno-codegen")
+        .arg("--out-dir")
+        .arg(out_dir)
+        .arg(program);
+    for var in ["LINK_ITEMS", "LINK_INST", "DEBUG", "STABLE_IDS"] {
+        cmd.env_remove(var);
+    }
+    for var in env {
+        cmd.env(var, "1");
+    }
+    let status = cmd.status().expect("failed to run stable_mir_json");
+    assert!(
+        status.success(),
+        "conversion failed for {}",
+        program.display()
+    );
+    out_dir.join(program.with_extension("smir.json").file_name().unwrap())
+}
+
+/// Run the driver on `tests/resources/<name>.rs` (see
+/// [`run_driver_with_args`]) and read its output, checking that the reader
+/// writes it back byte for byte. Each test and each combination of `env` and
+/// `args` writes into its own directory, so tests can run in parallel.
+pub fn run_resource(name: &str, env: &[&str], args: &[&str]) -> SmirJson {
+    run_and_read(&["tests", "resources"], name, env, args)
+}
+
+/// Like [`run_resource`], for `tests/integration/programs/<name>.rs`.
+pub fn run_program(name: &str, env: &[&str], args: &[&str]) -> SmirJson {
+    run_and_read(&["tests", "integration", "programs"], name, env, args)
+}
+
+fn run_and_read(dir: &[&str], name: &str, env: &[&str], args: &[&str]) -> SmirJson {
+    let file = format!("{name}.rs");
+    let program = get_resource_path([dir, &[file.as_str()]].concat());
+    let test = std::thread::current().name().unwrap_or("main").to_string();
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(dir.last().unwrap())
+        .join(name)
+        .join([&[test.as_str()], env, args].concat().join(","));
+    let output = run_driver_with_args(program.as_ref(), &out_dir, env, args);
+    let json = std::fs::read_to_string(&output).unwrap();
+    let smir = stable_mir_json_reader::from_str(&json).unwrap();
+    assert_eq!(stable_mir_json_reader::to_string(&smir).unwrap(), json);
+    smir
+}
+
+/// The `Ty` of the unsigned integer type `uint` in `smir`'s `types`.
+pub fn uint_ty(smir: &SmirJson, uint: UintTy) -> Ty {
+    smir.types
+        .iter()
+        .find(|(_, metadata)| *metadata == TypeMetadata::PrimitiveType(RigidTy::Uint(uint)))
+        .unwrap()
+        .0
 }
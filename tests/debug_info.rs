@@ -0,0 +1,59 @@
+//! `DEBUG` adds the debug section, which the reader parses.
+
+mod common;
+
+use common::run_resource;
+use stable_mir_json_reader::schema::ItemSource;
+use stable_mir_json_reader::FnSymType;
+
+/// Items that rustc has no optimized MIR for (statics, trait methods without
+/// a default body) get their type printed too.
+#[test]
+fn item_details_without_optimized_mir() {
+    let smir = run_resource("debug_types", &["DEBUG"], &[]);
+
+    let internal_ty = |needle: &str| {
+        let item = smir
+            .items
+            .iter()
+            .find(|item| item.symbol_name.contains(needle))
+            .unwrap_or_else(|| panic!("no item {needle}"));
+        item.details
+            .as_ref()
+            .expect("no details")
+            .internal_ty
+            .as_str()
+    };
+    assert_eq!(internal_ty("11debug_types7COUNTER"), "\nTyKind: u32");
+    assert!(
+        internal_ty("6FnOnce9call_once").starts_with("\nTyKind(FnDef): extern \"RustCall\" fn(")
+    );
+}
+
+#[test]
+fn debug_section_round_trips() {
+    let smir = run_resource("shims", &["DEBUG", "LINK_ITEMS", "LINK_INST"], &[]);
+
+    let debug = smir.debug.expect("no debug section");
+    assert_eq!(debug.fn_sources.len(), smir.functions.len());
+    // with `LINK_ITEMS`, `main` is in `functions` as an item
+    let main = smir
+        .items
+        .iter()
+        .find(|item| item.symbol_name.contains("5shims4main"))
+        .unwrap();
+    let main_sym = FnSymType::NormalSym(main.symbol_name.clone());
+    let (key, ..) = smir
+        .functions
+        .iter()
+        .find(|(_, sym, _)| *sym == main_sym)
+        .expect("main is not in functions");
+    let sources = debug
+        .fn_sources
+        .iter()
+        .find_map(|(k, sources)| (k == key).then_some(sources))
+        .unwrap();
+    assert!(sources.contains(&ItemSource::Item));
+    // every instance has a single symbol
+    assert!(debug.link_conflicts.is_empty());
+}
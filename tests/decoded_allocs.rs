@@ -0,0 +1,166 @@
+//! Memory allocations are decoded into values of their type.
+
+mod common;
+
+use common::run_resource;
+use stable_mir_json_reader::mir::alloc::GlobalAlloc;
+use stable_mir_json_reader::{DecodedValue, SmirJson};
+
+fn decoded(smir: &SmirJson) -> Vec<&DecodedValue> {
+    smir.allocs
+        .iter()
+        .filter_map(|alloc| alloc.decoded.as_ref())
+        .collect()
+}
+
+fn uint(value: u128) -> DecodedValue {
+    DecodedValue::Uint(value)
+}
+
+/// The unique decoded array whose elements satisfy `pred`.
+fn find_array<'a>(
+    values: &[&'a DecodedValue],
+    pred: impl Fn(&[DecodedValue]) -> bool,
+) -> &'a [DecodedValue] {
+    let found = values
+        .iter()
+        .filter_map(|value| match value {
+            DecodedValue::Array(elems) if pred(elems) => Some(elems.as_slice()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(found.len(), 1, "expected one match, got {found:?}");
+    found[0]
+}
+
+#[test]
+fn allocations_decode_to_typed_values() {
+    let smir = run_resource("decoded_allocs", &[], &[]);
+    let values = decoded(&smir);
+
+    // tuples of integers and chars
+    let pairs = find_array(&values, |elems| {
+        matches!(elems.first(), Some(DecodedValue::Aggregate(fields))
+            if fields.get(1) == Some(&DecodedValue::Char('a')))
+    });
+    assert_eq!(
+        pairs,
+        [
+            DecodedValue::Aggregate(vec![uint(1), DecodedValue::Char('a')]),
+            DecodedValue::Aggregate(vec![uint(2), DecodedValue::Char('b')]),
+        ]
+    );
+
+    // string slices are wide pointers to `str` allocations
+    let words = find_array(&values, |elems| {
+        elems.len() == 2
+            && elems.iter().all(|elem| {
+                matches!(elem, DecodedValue::Ptr { metadata: Some(m), .. } if **m != uint(0))
+            })
+    });
+    let mut strs = Vec::new();
+    for (word, len) in words.iter().zip([1, 2]) {
+        let DecodedValue::Ptr {
+            alloc_id: Some(alloc_id),
+            offset: 0,
+            metadata: Some(metadata),
+        } = word
+        else {
+            panic!("expected a pointer into an allocation, got {word:?}");
+        };
+        assert_eq!(**metadata, uint(len));
+        let target = smir
+            .allocs
+            .iter()
+            .find(|a| a.alloc_id == *alloc_id)
+            .unwrap();
+        strs.push(target.decoded.clone());
+    }
+    assert!(strs.contains(&Some(DecodedValue::Str("x".into()))));
+    assert!(strs.contains(&Some(DecodedValue::Str("yz".into()))));
+
+    // enum variants with their fields
+    let shapes = find_array(&values, |elems| {
+        matches!(elems.first(), Some(DecodedValue::Enum { variant: 0, .. }))
+            && matches!(elems.get(1), Some(DecodedValue::Enum { variant: 1, .. }))
+            && elems.len() == 2
+            && matches!(&elems[1], DecodedValue::Enum { fields, .. } if fields.len() == 2)
+    });
+    assert_eq!(
+        shapes[1],
+        DecodedValue::Enum {
+            variant: 1,
+            fields: vec![uint(3), uint(4)],
+        }
+    );
+
+    // signed integers and floats
+    assert!(values.contains(&&DecodedValue::Aggregate(vec![
+        DecodedValue::Int(-3),
+        DecodedValue::Float("-0.5".into()),
+    ])));
+    assert!(values.contains(&&DecodedValue::Array(vec![
+        DecodedValue::Bool(true),
+        DecodedValue::Bool(false),
+    ])));
+
+    // `Some(&7)` is told apart from the niche `None` by its provenance
+    let maybe = find_array(
+        &values,
+        |elems| matches!(elems.get(1), Some(DecodedValue::Enum { variant: 0, fields }) if fields.is_empty()),
+    );
+    let DecodedValue::Enum { variant: 1, fields } = &maybe[0] else {
+        panic!("expected `Some`, got {:?}", maybe[0]);
+    };
+    assert!(matches!(
+        fields.as_slice(),
+        [DecodedValue::Ptr {
+            alloc_id: Some(_),
+            metadata: None,
+            ..
+        }]
+    ));
+
+    // a function pointer points at the function's allocation
+    let funcs = find_array(&values, |elems| {
+        elems.len() == 1
+            && matches!(
+                &elems[0],
+                DecodedValue::Ptr {
+                    alloc_id: Some(_),
+                    metadata: None,
+                    ..
+                }
+            )
+    });
+    let DecodedValue::Ptr {
+        alloc_id: Some(alloc_id),
+        ..
+    } = &funcs[0]
+    else {
+        unreachable!()
+    };
+    assert!(smir.allocs.iter().any(|a| a.alloc_id == *alloc_id));
+}
+
+#[test]
+fn unrecovered_pointees_are_not_decoded() {
+    let smir = run_resource("union_pointer", &[], &[]);
+    let answer = 0x1122_3344_5566_7788_u64;
+    let bytes = answer.to_le_bytes().map(Some);
+
+    // the allocation behind the union's pointer has no known type
+    let decoded = smir
+        .allocs
+        .iter()
+        .filter(|alloc| {
+            matches!(&alloc.global_alloc, GlobalAlloc::Memory(memory) if memory.bytes == bytes)
+        })
+        .map(|alloc| alloc.decoded.clone())
+        .collect::<Vec<_>>();
+    assert!(decoded.contains(&None), "got {decoded:?}");
+    assert!(decoded
+        .iter()
+        .flatten()
+        .all(|value| *value == uint(answer.into())));
+}
@@ -0,0 +1,84 @@
+//! Running the driver in-process and getting the collected output back.
+#![feature(rustc_private)]
+
+mod common;
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use common::get_resource_path;
+use stable_mir_json::driver::{stable_mir_driver, DriverError};
+use stable_mir_json::printer::{collect_smir, OutputFlags};
+
+fn rustc_args(program: &str) -> Vec<String> {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("driver-api");
+    vec![
+        "stable_mir_json".into(),
+        "-Zno-codegen".into(),
+        "--out-dir".into(),
+        out_dir.to_str().unwrap().into(),
+        program.into(),
+    ]
+}
+
+/// Records what is written and the size of the largest single write.
+#[derive(Default)]
+struct ChunkRecorder {
+    bytes: Vec<u8>,
+    largest_write: usize,
+}
+
+impl Write for ChunkRecorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.largest_write = self.largest_write.max(buf.len());
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Scenarios run sequentially in one test: rustc does not promise that
+// concurrent compilations within one process are isolated.
+#[test]
+fn driver_api() {
+    let program = get_resource_path(vec!["tests", "integration", "programs", "binop.rs"]);
+    let flags = OutputFlags {
+        link_items: true,
+        ..OutputFlags::default()
+    };
+    let smir = stable_mir_driver(&rustc_args(&program), |tcx| collect_smir(tcx, &flags))
+        .expect("driver failed");
+    assert_eq!(smir.name, "binop");
+    assert!(smir.header.flags.link_items);
+    assert!(!smir.items.is_empty());
+    let json = smir.to_json();
+    let read = stable_mir_json_reader::from_str(&json).unwrap();
+    assert_eq!(read.items.len(), smir.items.len());
+
+    // `write_json` hands the writer small pieces, never the whole document.
+    let mut writer = ChunkRecorder::default();
+    smir.write_json(&mut writer).unwrap();
+    assert_eq!(writer.bytes, json.as_bytes());
+    assert!(
+        writer.largest_write * 100 < json.len(),
+        "largest write {} of {}",
+        writer.largest_write,
+        json.len()
+    );
+
+    let broken = Path::new(env!("CARGO_TARGET_TMPDIR")).join("driver-api-broken.rs");
+    std::fs::write(&broken, "fn main() { let x: u8 = \"not a number\"; }\n").unwrap();
+    let result = stable_mir_driver(&rustc_args(broken.to_str().unwrap()), |_| ());
+    assert!(matches!(result, Err(DriverError::Failed)), "{result:?}");
+
+    let args = [
+        "stable_mir_json".to_string(),
+        "--print=crate-name".into(),
+        program,
+    ];
+    let result = stable_mir_driver(&args, |_| ());
+    assert!(matches!(result, Err(DriverError::Skipped)), "{result:?}");
+}
@@ -0,0 +1,68 @@
+//! Enum types carry a decoded tag encoding and per-variant field offsets.
+
+mod common;
+
+use common::run_resource;
+use stable_mir_json_reader::abi::{Primitive, TagEncoding};
+use stable_mir_json_reader::{EnumLayout, SmirJson, TypeMetadata};
+
+fn enum_layout<'a>(smir: &'a SmirJson, type_name: &str) -> &'a EnumLayout {
+    smir.types
+        .iter()
+        .find_map(|(_, metadata)| match metadata {
+            TypeMetadata::EnumType {
+                name, enum_layout, ..
+            } if name == type_name => enum_layout.as_ref(),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no layout for {type_name}"))
+}
+
+#[test]
+fn enum_tags_and_variant_layouts() {
+    let smir = run_resource("enum_layouts", &[], &[]);
+
+    // a direct tag holds the discriminant, truncated to the tag's size
+    let signed = enum_layout(&smir, "Signed");
+    let tag = signed.tag.as_ref().unwrap();
+    assert_eq!(tag.encoding, TagEncoding::Direct);
+    assert_eq!((tag.offset, tag.size), (0, 1));
+    assert!(matches!(tag.primitive, Primitive::Int { signed: true, .. }));
+    let tag_values = signed
+        .variants
+        .iter()
+        .map(|v| v.as_ref().unwrap().tag_value)
+        .collect::<Vec<_>>();
+    assert_eq!(tag_values, [Some(0xfe), Some(0), Some(100)]);
+    assert_eq!(signed.variants[2].as_ref().unwrap().field_offsets, [4]);
+
+    // `None` is stored as a null pointer in `Some`'s field
+    let option = enum_layout(&smir, "std::option::Option<&u32>");
+    let tag = option.tag.as_ref().unwrap();
+    let TagEncoding::Niche {
+        untagged_variant,
+        niche_start,
+        ..
+    } = &tag.encoding
+    else {
+        panic!("expected a niche encoding, got {:?}", tag.encoding);
+    };
+    assert_eq!((untagged_variant.0, *niche_start), (1, 0));
+    assert_eq!((tag.offset, tag.size), (0, 8));
+    let none = option.variants[0].as_ref().unwrap();
+    let some = option.variants[1].as_ref().unwrap();
+    assert_eq!(none.tag_value, Some(0));
+    assert_eq!(
+        (some.tag_value, some.field_offsets.as_slice()),
+        (None, &[0][..])
+    );
+
+    // with a single inhabited variant there is no tag
+    let only_one = enum_layout(&smir, "OnlyOne");
+    assert!(only_one.tag.is_none());
+    assert!(only_one.variants[0].is_none());
+    let here = only_one.variants[1].as_ref().unwrap();
+    assert_eq!(here.tag_value, None);
+    assert_eq!(here.field_offsets.len(), 2);
+    assert!(!here.uninhabited);
+}
@@ -0,0 +1,60 @@
+//! Pointers stored in enum payloads get the pointee type of the active variant.
+
+mod common;
+
+use common::run_program;
+use stable_mir_json_reader::ty::{RigidTy, UintTy};
+use stable_mir_json_reader::{DecodedValue, SmirJson, TypeMetadata};
+
+/// The type metadata of the allocation whose contents decode to `value`.
+fn alloc_type<'a>(smir: &'a SmirJson, value: &DecodedValue) -> &'a TypeMetadata {
+    let alloc = smir
+        .allocs
+        .iter()
+        .find(|alloc| alloc.decoded.as_ref() == Some(value))
+        .unwrap_or_else(|| panic!("no allocation holding {value:?}"));
+    smir.types
+        .iter()
+        .find_map(|(ty, metadata)| (*ty == alloc.ty).then_some(metadata))
+        .unwrap_or_else(|| panic!("no type for {:?}", alloc.ty))
+}
+
+fn is_u8(smir: &SmirJson, ty: &stable_mir_json_reader::ty::Ty) -> bool {
+    smir.types.iter().any(|(t, metadata)| {
+        t == ty && *metadata == TypeMetadata::PrimitiveType(RigidTy::Uint(UintTy::U8))
+    })
+}
+
+#[test]
+fn enum_payload_pointees_are_typed() {
+    let smir = run_program("enum_provenance", &[], &[]);
+
+    // `Some("hello")`: the pointer is `Some`'s field, a niche-encoded enum
+    assert_eq!(
+        *alloc_type(&smir, &DecodedValue::Str("hello".into())),
+        TypeMetadata::PrimitiveType(RigidTy::Str)
+    );
+
+    // `Ok(b"bytes")`: the slice follows a direct tag
+    let bytes = DecodedValue::Array(
+        b"bytes"
+            .iter()
+            .map(|b| DecodedValue::Uint((*b).into()))
+            .collect(),
+    );
+    let TypeMetadata::ArrayType {
+        elem_type,
+        size: None,
+        ..
+    } = alloc_type(&smir, &bytes)
+    else {
+        panic!("expected `[u8]`");
+    };
+    assert!(is_u8(&smir, elem_type));
+
+    // `Err("nested")` in a slice of enums, after an `Ok` element
+    assert_eq!(
+        *alloc_type(&smir, &DecodedValue::Str("nested".into())),
+        TypeMetadata::PrimitiveType(RigidTy::Str)
+    );
+}
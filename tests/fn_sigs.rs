@@ -0,0 +1,90 @@
+//! Function definitions, function pointers and closures carry their
+//! signatures in `types`, and function items carry their ABI.
+
+mod common;
+
+use common::{run_resource, uint_ty};
+use stable_mir_json_reader::abi::CallConvention;
+use stable_mir_json_reader::mir::Safety;
+use stable_mir_json_reader::ty::{Abi, UintTy};
+use stable_mir_json_reader::{FnSignature, MonoItemKind, SmirJson, TypeMetadata};
+
+fn fn_sig(smir: &SmirJson, pred: impl Fn(&str) -> bool) -> &FnSignature {
+    smir.types
+        .iter()
+        .find_map(|(_, metadata)| match metadata {
+            TypeMetadata::FnSigType { name, sig, .. } if pred(name) => Some(sig),
+            _ => None,
+        })
+        .expect("no such function type")
+}
+
+#[test]
+fn fn_signatures_and_abis() {
+    let smir = run_resource("fn_sigs", &[], &["--edition=2021"]);
+    let u32_ty = uint_ty(&smir, UintTy::U32);
+
+    let add_c = fn_sig(&smir, |name| name.ends_with("{add_c}"));
+    assert_eq!(add_c.inputs, [u32_ty, u32_ty]);
+    assert_eq!(add_c.output, u32_ty);
+    assert_eq!(add_c.abi, Abi::C { unwind: false });
+    assert!(!add_c.c_variadic);
+
+    let first = fn_sig(&smir, |name| name.ends_with("{first}"));
+    assert_eq!(first.safety, Safety::Unsafe);
+
+    let printf = fn_sig(&smir, |name| name.ends_with("{printf}"));
+    assert!(printf.c_variadic);
+    assert_eq!(printf.inputs.len(), 1);
+
+    // the pointer `apply` takes, with its layout
+    let pointer = smir.types.iter().find_map(|(_, metadata)| match metadata {
+        TypeMetadata::FnSigType { name, sig, layout } if name == "fn(u32) -> u32" => {
+            Some((sig, layout))
+        }
+        _ => None,
+    });
+    let (sig, layout) = pointer.expect("no function pointer type");
+    assert_eq!((sig.inputs.as_slice(), sig.output), (&[u32_ty][..], u32_ty));
+    assert_eq!(sig.abi, Abi::Rust);
+    assert!(layout.is_some());
+
+    // a closure takes its arguments as one tuple
+    let closure = smir.types.iter().find_map(|(_, metadata)| match metadata {
+        TypeMetadata::ClosureType { name, sig, .. } if name.contains("fn_sigs.rs") => Some(sig),
+        _ => None,
+    });
+    let sig = closure.expect("no closure type");
+    assert_eq!(sig.abi, Abi::RustCall);
+    let [args] = sig.inputs.as_slice() else {
+        panic!("closure inputs {:?}", sig.inputs);
+    };
+    assert!(smir.types.iter().any(|(ty, metadata)| ty == args
+        && matches!(metadata, TypeMetadata::TupleType { types, .. } if types.len() == 2)));
+
+    // the ABI of a defined function
+    let fn_abi = smir
+        .items
+        .iter()
+        .find_map(|item| match &item.mono_item_kind {
+            MonoItemKind::MonoItemFn { name, fn_abi, .. } if name == "add_c" => fn_abi.as_ref(),
+            _ => None,
+        })
+        .expect("no ABI for add_c");
+    assert_eq!(fn_abi.conv, CallConvention::C);
+    assert_eq!(fn_abi.args.len(), 2);
+    assert!(fn_abi.args.iter().all(|arg| arg.ty == u32_ty));
+    assert_eq!(fn_abi.ret.ty, u32_ty);
+    // layouts are inlined, not interned handles
+    assert!(fn_abi.args.iter().all(|arg| arg.layout.size.num_bits == 32));
+    assert_eq!(fn_abi.ret.layout.size.num_bits, 32);
+
+    // every type a signature mentions has an entry
+    for (_, metadata) in &smir.types {
+        if let TypeMetadata::FnSigType { sig, .. } = metadata {
+            for ty in sig.inputs.iter().chain([&sig.output]) {
+                assert!(smir.types.iter().any(|(t, _)| t == ty), "dangling {ty:?}");
+            }
+        }
+    }
+}
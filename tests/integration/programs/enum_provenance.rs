@@ -0,0 +1,16 @@
+const GREETING: Option<&str> = Some("hello");
+const NOTHING: Option<&str> = None;
+const PAYLOAD: Result<&[u8], u16> = Ok(b"bytes");
+const FAILURE: Result<&[u8], u16> = Err(7);
+const NESTED: &[Result<u8, &str>] = &[Ok(1), Err("nested")];
+
+fn len(value: Option<&str>) -> usize {
+    value.map_or(0, str::len)
+}
+
+fn main() {
+    let mut total = len(GREETING) + len(NOTHING);
+    total += PAYLOAD.map_or(0, <[u8]>::len) + FAILURE.map_or(0, <[u8]>::len);
+    total += NESTED.iter().map(|r| r.map_or_else(str::len, usize::from)).sum::<usize>();
+    assert_eq!(total, 5 + 5 + 1 + 6);
+}
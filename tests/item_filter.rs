@@ -0,0 +1,102 @@
+//! `--smir-filter-path` and `--smir-reachable-from` restrict the output to
+//! part of the crate, with the shared tables pruned to match.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{get_resource_path, out_dir, run_driver_with_args, test_programs};
+use stable_mir_json_reader::{ItemFilter, MonoItemKind, SmirJson};
+
+fn run(program: &Path, test: &str, args: &[&str]) -> SmirJson {
+    let output = run_driver_with_args(
+        program,
+        &out_dir("item-filter", test),
+        &["STABLE_IDS"],
+        args,
+    );
+    stable_mir_json_reader::load(output).unwrap()
+}
+
+fn item_names(smir: &SmirJson) -> Vec<&str> {
+    let mut names = smir
+        .items
+        .iter()
+        .filter_map(|item| match &item.mono_item_kind {
+            MonoItemKind::MonoItemFn { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Everything rustc collects is reachable from the program's entry points,
+/// so filtering by reachability from them must not change the output.
+#[test]
+fn entry_points_reach_everything() {
+    for program in test_programs() {
+        let all = run(&program, "entry-points-all", &[]);
+        let reachable = run(
+            &program,
+            "entry-points",
+            &[
+                "--smir-reachable-from=std::rt::lang_start",
+                "--smir-reachable-from=main",
+            ],
+        );
+        assert_eq!(
+            reachable.header.flags.filter.reachable_from,
+            ["std::rt::lang_start", "main"]
+        );
+        let name = program.display();
+        assert!(reachable.items == all.items, "{name}: items differ");
+        assert!(
+            reachable.functions == all.functions,
+            "{name}: functions differ"
+        );
+        assert!(reachable.types == all.types, "{name}: types differ");
+        assert!(reachable.allocs == all.allocs, "{name}: allocs differ");
+        assert!(reachable.spans == all.spans, "{name}: spans differ");
+    }
+}
+
+#[test]
+fn filters_prune_output() {
+    let program: PathBuf = get_resource_path(vec![
+        "tests",
+        "integration",
+        "programs",
+        "mutual_recursion.rs",
+    ])
+    .into();
+    let all = run(&program, "prune-all", &[]);
+
+    let reachable = run(&program, "is-even", &["--smir-reachable-from=is_even"]);
+    assert_eq!(item_names(&reachable), ["is_even", "is_odd"]);
+
+    let globbed = run(&program, "glob", &["--smir-filter-path=is_*"]);
+    assert_eq!(
+        globbed.header.flags.filter,
+        ItemFilter {
+            paths: vec!["is_*".to_string()],
+            reachable_from: vec![],
+        }
+    );
+    assert!(globbed.items == reachable.items);
+
+    for smir in [&reachable, &globbed] {
+        assert!(smir.types.len() < all.types.len());
+        assert!(smir.spans.len() < all.spans.len());
+        assert!(smir.functions.len() < all.functions.len());
+        assert!(smir.allocs.is_empty());
+    }
+
+    // Globs bound the traversal: `main` is kept, but not what only it calls.
+    let bounded = run(
+        &program,
+        "bounded",
+        &["--smir-reachable-from=main", "--smir-filter-path=main"],
+    );
+    assert_eq!(item_names(&bounded), ["main"]);
+}
@@ -0,0 +1,54 @@
+//! Validate the driver's output for every golden test program against the
+//! JSON Schema generated by `stable_mir_json_reader::json_schema`.
+//!
+//! The `*.smir.json.expected` files themselves are normalised (IDs deleted,
+//! fields elided, mangled names truncated) and so are not valid instances of
+//! the schema; instead each program with a golden file is converted afresh
+//! and the raw output is validated.
+
+mod common;
+
+use std::path::Path;
+
+use common::{run_driver, test_programs};
+
+fn check_schema(mode: &str, env: &[&str]) {
+    let schema = stable_mir_json_reader::json_schema();
+    let validator =
+        jsonschema::validator_for(schema.as_value()).expect("generated schema is invalid");
+    assert!(!validator.is_valid(&json::json!({})));
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("json-schema")
+        .join(mode);
+    let golden_programs = test_programs()
+        .into_iter()
+        .filter(|program| program.with_extension("smir.json.expected").exists());
+    let mut checked = 0;
+    for program in golden_programs {
+        let output = run_driver(&program, &out_dir, env);
+        let json: json::Value = json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        let errors = validator
+            .iter_errors(&json)
+            .take(5)
+            .map(|e| format!("  {} at {}", e, e.instance_path()))
+            .collect::<Vec<_>>();
+        assert!(
+            errors.is_empty(),
+            "{} ({mode}) does not match the schema:\n{}",
+            program.display(),
+            errors.join("\n")
+        );
+        checked += 1;
+    }
+    assert!(checked > 0, "no golden test programs found");
+}
+
+#[test]
+fn schema_default() {
+    check_schema("default", &[]);
+}
+
+#[test]
+fn schema_link_flags() {
+    check_schema("link-flags", &["LINK_ITEMS", "LINK_INST"]);
+}
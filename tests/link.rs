@@ -0,0 +1,96 @@
+//! `stable_mir_json_reader::link` merges the outputs of a library and a
+//! binary using it into one program.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{get_resource_path, out_dir, run_driver_with_args};
+use stable_mir_json_reader::{link, FnSymType, LinkError, SmirJson};
+
+fn resource(name: &str) -> PathBuf {
+    get_resource_path(vec!["tests", "resources", name]).into()
+}
+
+/// The outputs for `link_main.rs` and the `link_lib.rs` library it uses.
+fn build(test: &str, env: &[&str]) -> (SmirJson, SmirJson) {
+    let dir = out_dir("link", test);
+    let lib = run_driver_with_args(&resource("link_lib.rs"), &dir, env, &["--crate-type=rlib"]);
+    let rlib = format!("link_lib={}", dir.join("liblink_lib.rlib").display());
+    let main = run_driver_with_args(&resource("link_main.rs"), &dir, env, &["--extern", &rlib]);
+    (
+        stable_mir_json_reader::load(main).unwrap(),
+        stable_mir_json_reader::load(lib).unwrap(),
+    )
+}
+
+fn normal_sym<'a>(smir: &'a SmirJson, needle: &str) -> &'a str {
+    smir.functions
+        .iter()
+        .find_map(|(_, sym, _)| match sym {
+            FnSymType::NormalSym(name) if name.contains(needle) => Some(name.as_str()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no call to {needle}"))
+}
+
+#[test]
+fn links_calls_across_crates() {
+    let (main, lib) = build("stable", &["STABLE_IDS"]);
+    let double = normal_sym(&main, "link_lib6double").to_string();
+    assert!(!main.items.iter().any(|item| item.symbol_name == double));
+
+    let linked = link(vec![main.clone(), lib.clone()]).unwrap();
+    let smir = &linked.smir;
+    assert_eq!(smir.name, "link_main");
+    assert_eq!(smir.header, main.header);
+    assert_eq!(smir.items.len(), main.items.len() + lib.items.len());
+    assert!(smir.items.iter().any(|item| item.symbol_name == double));
+    assert!(!linked.unresolved.contains(&double));
+    assert!(linked
+        .unresolved
+        .iter()
+        .all(|sym| !sym.contains("link_lib")));
+    assert!(linked
+        .unresolved
+        .iter()
+        .any(|sym| sym.contains("lang_start_internal")));
+
+    // Shared types are merged, and every reference is to a merged table.
+    let mut tys = smir.types.iter().map(|(ty, _)| *ty).collect::<Vec<_>>();
+    tys.sort();
+    tys.dedup();
+    assert_eq!(tys.len(), smir.types.len());
+    assert!(smir.types.len() < main.types.len() + lib.types.len());
+    for (ty, _) in main.types.iter().chain(&lib.types) {
+        assert!(smir.types.iter().any(|(t, _)| t == ty));
+    }
+
+    // A single input links to itself.
+    assert_eq!(link(vec![lib.clone()]).unwrap().smir, lib);
+}
+
+#[test]
+fn rejects_unlinkable_inputs() {
+    assert_eq!(link(vec![]), Err(LinkError::NoInputs));
+
+    let (main, lib) = build("interned", &[]);
+    assert_eq!(
+        link(vec![main, lib]),
+        Err(LinkError::NotStableIds("link_main".to_string()))
+    );
+
+    let (_, lib) = build("conflict", &["STABLE_IDS"]);
+    let mut other = lib.clone();
+    other.name = "other".to_string();
+    let (ty, _) = other.types[0];
+    other.types[0].1 = other.types[1].1.clone();
+    assert_eq!(
+        link(vec![lib, other]),
+        Err(LinkError::Conflict {
+            name: "other".to_string(),
+            table: "type",
+            id: ty.0,
+        })
+    );
+}
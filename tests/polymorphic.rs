@@ -0,0 +1,99 @@
+//! `--smir-polymorphic` adds the generic functions of the crate, with their
+//! polymorphic bodies, generics and predicates, to `generic_items`.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{get_resource_path, run_driver_with_args};
+use stable_mir_json_reader::ty::{ClauseKind, PredicateKind, Ty};
+use stable_mir_json_reader::{MonoItemKind, SmirJson, TypeMetadata};
+
+fn program() -> PathBuf {
+    get_resource_path(vec!["tests", "resources", "generic_items.rs"]).into()
+}
+
+fn run(test: &str, args: &[&str]) -> SmirJson {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("polymorphic")
+        .join(test);
+    let output = run_driver_with_args(&program(), &out_dir, &["STABLE_IDS"], args);
+    stable_mir_json_reader::load(output).unwrap()
+}
+
+fn metadata(smir: &SmirJson, ty: Ty) -> &TypeMetadata {
+    &smir.types.iter().find(|(t, _)| *t == ty).unwrap().1
+}
+
+#[test]
+fn generic_items_only_with_flag() {
+    let mono = run("mono", &[]);
+    assert!(mono.generic_items.is_empty());
+    assert!(!mono.header.flags.polymorphic);
+
+    let poly = run("poly", &["--smir-polymorphic"]);
+    assert!(poly.header.flags.polymorphic);
+    // The mono items are unaffected.
+    assert!(poly.items == mono.items);
+    assert!(poly.functions == mono.functions);
+
+    let mut names = poly
+        .generic_items
+        .iter()
+        .map(|item| item.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "Wrap::<T>::get",
+            "areas",
+            "areas::{closure#0}",
+            "first",
+            "unused"
+        ]
+    );
+    let unused_is_mono = mono.items.iter().any(|item| {
+        matches!(&item.mono_item_kind, MonoItemKind::MonoItemFn { name, .. } if name == "unused")
+    });
+    assert!(!unused_is_mono);
+}
+
+#[test]
+fn generics_predicates_and_types() {
+    let smir = run("types", &["--smir-polymorphic", "--smir-filter-path=*get"]);
+    let [get] = &smir.generic_items[..] else {
+        panic!("expected only `Wrap::<T>::get`");
+    };
+    // The impl block's generics come first and declare `T`.
+    assert_eq!(get.generics.len(), 2);
+    assert_eq!(get.generics[0].params[0].name, "T");
+    // `T: Clone` is declared on the impl block.
+    let bounded = get
+        .predicates
+        .iter()
+        .filter(|(pred, _)| matches!(pred, PredicateKind::Clause(ClauseKind::Trait(_))));
+    assert!(bounded.count() >= 2, "expected `T: Clone` and `T: Sized`");
+    // The return place has type `T`.
+    let ret = get.body.locals[0].ty;
+    match metadata(&smir, ret) {
+        TypeMetadata::ParamType(param) => assert_eq!((param.index, param.name.as_str()), (0, "T")),
+        other => panic!("unexpected metadata for `T`: {other:?}"),
+    }
+
+    let smir = run("alias", &["--smir-polymorphic", "--smir-filter-path=first"]);
+    let [first] = &smir.generic_items[..] else {
+        panic!("expected only `first`");
+    };
+    // `Option<I::Item>`: the projection stays unnormalized.
+    let ret = first.body.locals[0].ty;
+    let TypeMetadata::EnumType { fields, .. } = metadata(&smir, ret) else {
+        panic!("`Option<I::Item>` is not an enum");
+    };
+    match metadata(&smir, fields[1][0]) {
+        TypeMetadata::AliasType { name, .. } => {
+            assert_eq!(name, "<I as std::iter::Iterator>::Item")
+        }
+        other => panic!("unexpected metadata for `I::Item`: {other:?}"),
+    }
+}
@@ -0,0 +1,172 @@
+//! Round-trip the driver's output for every integration test program through
+//! `stable_mir_json_reader`: deserializing and re-serializing must reproduce
+//! the file byte for byte, in the default mode, with the link map flags set,
+//! with the polymorphic bodies of generic items, and with upstream bodies.
+//! The CBOR output written alongside must decode to the same value and
+//! re-encode to the same bytes, and the split output must reassemble into
+//! the same value.
+
+mod common;
+
+use std::path::Path;
+
+use common::{run_driver_with_args, test_programs};
+
+fn check_roundtrip(mode: &str, env: &[&str], args: &[&str]) {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("reader-roundtrip")
+        .join(mode);
+    // Split output refuses to replace an `items/` directory it did not mark,
+    // such as one left over from an older build.
+    let _ = std::fs::remove_dir_all(&out_dir);
+    for program in test_programs() {
+        let args = [&["--smir-format=json,cbor,split"], args].concat();
+        let output = run_driver_with_args(&program, &out_dir, env, &args);
+        let json = std::fs::read_to_string(&output).unwrap();
+        let smir = stable_mir_json_reader::load(&output)
+            .unwrap_or_else(|e| panic!("{}: {e}", output.display()));
+        check_header(&smir.header, env, &args);
+        let reserialized = stable_mir_json_reader::to_string(&smir).unwrap();
+        assert!(
+            json == reserialized,
+            "{} ({mode}) did not round-trip",
+            program.display()
+        );
+
+        let cbor_output = output.with_extension("cbor");
+        let cbor = std::fs::read(&cbor_output).unwrap();
+        let from_cbor = stable_mir_json_reader::load(&cbor_output)
+            .unwrap_or_else(|e| panic!("{}: {e}", cbor_output.display()));
+        assert!(
+            from_cbor == smir,
+            "{} ({mode}): CBOR and JSON output differ",
+            program.display()
+        );
+        assert!(
+            stable_mir_json_reader::to_cbor_vec(&from_cbor).unwrap() == cbor,
+            "{} ({mode}) did not round-trip through CBOR",
+            program.display()
+        );
+
+        let split_dir = output.with_extension("");
+        let from_split = stable_mir_json_reader::load_split(&split_dir)
+            .unwrap_or_else(|e| panic!("{}: {e}", split_dir.display()));
+        assert!(
+            from_split == smir,
+            "{} ({mode}): split and JSON output differ",
+            program.display()
+        );
+    }
+}
+
+fn check_header(header: &stable_mir_json_reader::SmirJsonHeader, env: &[&str], args: &[&str]) {
+    assert_eq!(
+        header.schema_version,
+        stable_mir_json_reader::SCHEMA_VERSION
+    );
+    assert_eq!(header.producer, "stable_mir_json");
+    assert_eq!(header.producer_version, env!("CARGO_PKG_VERSION"));
+    let flags = &header.flags;
+    assert_eq!(flags.link_items, env.contains(&"LINK_ITEMS"));
+    assert_eq!(flags.link_inst, env.contains(&"LINK_INST"));
+    assert_eq!(flags.debug, env.contains(&"DEBUG"));
+    assert_eq!(flags.stable_ids, env.contains(&"STABLE_IDS"));
+    assert_eq!(flags.polymorphic, args.contains(&"--smir-polymorphic"));
+    assert_eq!(flags.upstream.is_some(), args.contains(&"--smir-upstream"));
+}
+
+#[test]
+fn roundtrip_default() {
+    check_roundtrip("default", &[], &[]);
+}
+
+#[test]
+fn roundtrip_link_flags() {
+    check_roundtrip("link-flags", &["LINK_ITEMS", "LINK_INST"], &[]);
+}
+
+#[test]
+fn roundtrip_stable_ids() {
+    check_roundtrip("stable-ids", &["STABLE_IDS"], &[]);
+}
+
+#[test]
+fn roundtrip_polymorphic() {
+    check_roundtrip("polymorphic", &[], &["--smir-polymorphic"]);
+}
+
+#[test]
+fn roundtrip_upstream() {
+    check_roundtrip("upstream", &[], &["--smir-upstream"]);
+}
+
+#[test]
+fn rejects_unknown_schema_version() {
+    let json = r#"{"header":{"schema_version":999,"producer":"stable_mir_json","producer_version":"0","rustc_version":"","rustc_commit":"","flags":{"link_items":false,"link_inst":false,"debug":false,"stable_ids":false}}}"#;
+    match stable_mir_json_reader::from_str(json) {
+        Err(stable_mir_json_reader::Error::UnsupportedSchema { found, expected }) => {
+            assert_eq!(found, 999);
+            assert_eq!(expected, stable_mir_json_reader::SCHEMA_VERSION);
+        }
+        other => panic!("expected UnsupportedSchema, got {other:?}"),
+    }
+}
+
+#[test]
+fn reports_version_of_unreadable_file() {
+    // A future revision whose header and body no longer match this reader.
+    let json = r#"{"header":{"schema_version":999,"producer":{"name":"x"}},"items":{}}"#;
+    match stable_mir_json_reader::from_str(json) {
+        Err(stable_mir_json_reader::Error::UnsupportedSchema { found, .. }) => {
+            assert_eq!(found, 999)
+        }
+        other => panic!("expected UnsupportedSchema, got {other:?}"),
+    }
+    // A readable file of this revision with a malformed body reports where.
+    let program = &test_programs()[0];
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("reader-header");
+    let json = std::fs::read_to_string(run_driver_with_args(program, &out_dir, &[], &[])).unwrap();
+    let truncated = &json[..json.find(r#","items":"#).unwrap() + 10];
+    assert!(matches!(
+        stable_mir_json_reader::from_str(truncated),
+        Err(stable_mir_json_reader::Error::Json(_))
+    ));
+}
+
+#[test]
+fn reads_header_without_the_rest() {
+    let program = &test_programs()[0];
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("reader-header-only");
+    let json = std::fs::read_to_string(run_driver_with_args(program, &out_dir, &[], &[])).unwrap();
+    let header = stable_mir_json_reader::read_header(&json).unwrap();
+    assert_eq!(
+        header.schema_version,
+        stable_mir_json_reader::SCHEMA_VERSION
+    );
+    // Everything after the header is malformed, and not read.
+    let end = json.find(r#","name":"#).unwrap();
+    let truncated = format!("{} garbage", &json[..end + 1]);
+    assert_eq!(
+        stable_mir_json_reader::read_header(&truncated).unwrap(),
+        header
+    );
+}
+
+#[test]
+fn reads_cbor_header_without_the_rest() {
+    let program = &test_programs()[0];
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("reader-cbor-header");
+    let output = run_driver_with_args(program, &out_dir, &[], &["--smir-format=cbor"]);
+    let cbor = std::fs::read(output.with_extension("cbor")).unwrap();
+    let header = stable_mir_json_reader::read_cbor_header(&cbor).unwrap();
+    assert_eq!(
+        header.schema_version,
+        stable_mir_json_reader::SCHEMA_VERSION
+    );
+    // Cut off at the `name` key after the header, which is not read.
+    let end = cbor.windows(5).position(|w| w == b"\x64name").unwrap();
+    assert_eq!(
+        stable_mir_json_reader::read_cbor_header(&cbor[..end]).unwrap(),
+        header
+    );
+}
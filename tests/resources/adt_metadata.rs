@@ -0,0 +1,54 @@
+#[repr(C)]
+struct Pair<T> {
+    first: T,
+    second: u8,
+}
+
+#[repr(C, packed(2))]
+struct Packed(u8, u32);
+
+#[repr(align(16))]
+struct Aligned {
+    value: u64,
+}
+
+#[repr(u8)]
+enum Shape {
+    Point,
+    Circle { radius: u32 },
+    Rect(u16, u16),
+}
+
+#[repr(transparent)]
+struct Meters(f64);
+
+#[allow(dead_code)]
+union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn area(shape: &Shape) -> u32 {
+    match shape {
+        Shape::Point => 0,
+        Shape::Circle { radius } => 3 * radius * radius,
+        Shape::Rect(w, h) => *w as u32 * *h as u32,
+    }
+}
+
+fn main() {
+    let pair = Pair { first: 1u16, second: 2 };
+    let packed = Packed(3, 4);
+    let aligned = Aligned { value: 5 };
+    let meters = Meters(6.0);
+    let bits = Bits { int: 7 };
+    let shapes = [Shape::Point, Shape::Circle { radius: 1 }, Shape::Rect(2, 3)];
+    let total: u32 = shapes.iter().map(area).sum();
+    let int = unsafe { bits.int };
+    let packed_1 = packed.1;
+    assert_eq!(
+        pair.first as u32 + pair.second as u32 + packed.0 as u32 + packed_1 + aligned.value as u32,
+        15
+    );
+    assert_eq!(meters.0 as u32 + int + total, 22);
+}
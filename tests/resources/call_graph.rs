@@ -0,0 +1,37 @@
+trait Count {
+    fn count(&self) -> u32;
+}
+
+struct Guard(u32);
+
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+impl Count for Guard {
+    fn count(&self) -> u32 {
+        self.0
+    }
+}
+
+fn fact(n: u32) -> u32 {
+    if n == 0 {
+        1
+    } else {
+        n * fact(n - 1)
+    }
+}
+
+fn twice(f: fn(u32) -> u32, n: u32) -> u32 {
+    f(f(n))
+}
+
+fn total(counter: &dyn Count) -> u32 {
+    counter.count()
+}
+
+fn main() {
+    let guard = Guard(3);
+    let n = twice(fact, total(&guard));
+    assert!(n > 0);
+}
@@ -0,0 +1,43 @@
+#![allow(internal_features)]
+#![feature(extern_types, pattern_types, core_pattern_type, noop_waker)]
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+extern "C" {
+    type Opaque;
+}
+
+type Small = std::pat::pattern_type!(u32 is 1..=10);
+
+fn as_ptr(opaque: &Opaque) -> *const Opaque {
+    opaque
+}
+
+async fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+async fn compute(x: u32) -> u32 {
+    let y = add(x, 1).await;
+    let kept = [y, x];
+    add(kept[0], kept[1]).await
+}
+
+fn main() {
+    let offset = 5u32;
+    let scale = 2u8;
+    let shift = move |x: u32| x * scale as u32 + offset;
+
+    let mut future = pin!(compute(shift(1)));
+    let mut cx = Context::from_waker(Waker::noop());
+    let Poll::Ready(result) = future.as_mut().poll(&mut cx) else {
+        panic!("not ready");
+    };
+
+    let small: Small = unsafe { std::mem::transmute(3u32) };
+    let raw: u32 = unsafe { std::mem::transmute(small) };
+    assert_eq!(result + raw, 18);
+    assert!(as_ptr(unsafe { &*(8 as *const Opaque) }).is_null() == false);
+}
@@ -0,0 +1,21 @@
+static COUNTER: u32 = 7;
+
+trait Shape {
+    fn sides(&self) -> u32;
+}
+
+struct Square;
+
+impl Shape for Square {
+    fn sides(&self) -> u32 {
+        4
+    }
+}
+
+fn total(shape: &dyn Shape) -> u32 {
+    shape.sides() + COUNTER
+}
+
+fn main() {
+    assert_eq!(total(&Square), 11);
+}
@@ -0,0 +1,35 @@
+#[derive(Clone, Copy)]
+enum Shape {
+    Empty,
+    Rect { w: u16, h: u16 },
+}
+
+struct Point {
+    x: i16,
+    y: f64,
+}
+
+fn double(x: u32) -> u32 {
+    2 * x
+}
+
+const PAIRS: &[(u8, char)] = &[(1, 'a'), (2, 'b')];
+const WORDS: &[&str] = &["x", "yz"];
+const SHAPES: &[Shape; 2] = &[Shape::Empty, Shape::Rect { w: 3, h: 4 }];
+const POINT: &Point = &Point { x: -3, y: -0.5 };
+const MAYBE: &[Option<&u32>] = &[Some(&7), None];
+const FUNCS: &[fn(u32) -> u32] = &[double];
+const FLAGS: &[bool; 2] = &[true, false];
+
+fn main() {
+    let shape_area = |s: &Shape| match s {
+        Shape::Empty => 0,
+        Shape::Rect { w, h } => w * h,
+    };
+    let mut total = PAIRS[1].0 as u32 + PAIRS[0].1 as u32;
+    total += WORDS[1].len() as u32 + shape_area(&SHAPES[1]) as u32;
+    total += (POINT.x as f64 * POINT.y) as u32;
+    total += MAYBE.iter().flatten().map(|x| **x).sum::<u32>();
+    total += FUNCS[0](1) + FLAGS[0] as u32;
+    assert_eq!(total, 2 + 97 + 2 + 12 + 1 + 7 + 2 + 1);
+}
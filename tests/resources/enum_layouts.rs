@@ -0,0 +1,42 @@
+#[repr(i8)]
+#[derive(Clone, Copy)]
+enum Signed {
+    Neg = -2,
+    Zero = 0,
+    Big(u32) = 100,
+}
+
+enum Never {}
+
+#[allow(dead_code)]
+enum OnlyOne {
+    Gone(Never),
+    Here(u16, u8),
+}
+
+fn pick(s: Signed) -> i32 {
+    match s {
+        Signed::Neg => -2,
+        Signed::Zero => 0,
+        Signed::Big(n) => n as i32,
+    }
+}
+
+fn deref(r: Option<&u32>) -> u32 {
+    r.copied().unwrap_or(0)
+}
+
+fn here(o: OnlyOne) -> u16 {
+    match o {
+        OnlyOne::Here(a, b) => a + b as u16,
+        OnlyOne::Gone(n) => match n {},
+    }
+}
+
+fn main() {
+    let x = 7;
+    let total = pick(Signed::Neg) + pick(Signed::Zero) + pick(Signed::Big(3));
+    assert_eq!(total, 1);
+    assert_eq!(deref(Some(&x)) + deref(None), 7);
+    assert_eq!(here(OnlyOne::Here(1, 2)), 3);
+}
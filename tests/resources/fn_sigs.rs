@@ -0,0 +1,31 @@
+use std::ffi::{c_char, c_int};
+
+extern "C" {
+    fn printf(format: *const c_char, ...) -> c_int;
+}
+
+extern "C" fn add_c(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+unsafe fn first(p: *const u8) -> u8 {
+    *p
+}
+
+fn apply(f: fn(u32) -> u32, x: u32) -> u32 {
+    f(x)
+}
+
+fn double(x: u32) -> u32 {
+    add_c(x, x)
+}
+
+fn main() {
+    let offset = 1u8;
+    let shift = move |x: u8, y: u16| x as u16 + y + offset as u16;
+    let byte = unsafe { first(&7) };
+    let sum = apply(double, 2) + shift(byte, 3) as u32;
+    unsafe {
+        printf(c"%u\n".as_ptr(), sum);
+    }
+}
@@ -0,0 +1,41 @@
+trait Shape {
+    type Unit: Copy;
+    fn area(&self) -> Self::Unit;
+}
+
+struct Square(u32);
+
+impl Shape for Square {
+    type Unit = u32;
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+struct Wrap<T>(T);
+
+impl<T: Clone> Wrap<T> {
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+fn first<I: Iterator>(mut it: I) -> Option<I::Item> {
+    it.next()
+}
+
+fn areas<S: Shape>(shapes: &[S]) -> Vec<S::Unit> {
+    shapes.iter().map(|s| s.area()).collect()
+}
+
+// Never instantiated, so there is no mono item for it.
+#[allow(dead_code)]
+fn unused<T: Default>() -> T {
+    T::default()
+}
+
+fn main() {
+    let _ = first(vec![1, 2].into_iter());
+    let _ = areas(&[Square(2)]);
+    let _ = Wrap(1u8).get();
+}
@@ -0,0 +1,13 @@
+pub static GREETING: &str = "hello";
+
+pub fn double(x: u32) -> u32 {
+    x * 2
+}
+
+pub fn greeting_len() -> usize {
+    GREETING.len()
+}
+
+pub fn apply<F: Fn(u32) -> u32>(f: F, x: u32) -> u32 {
+    f(double(x))
+}
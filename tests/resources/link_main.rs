@@ -0,0 +1,6 @@
+extern crate link_lib;
+
+fn main() {
+    let x = link_lib::apply(|y| y + 1, 20);
+    assert_eq!(x + link_lib::greeting_len() as u32, 46);
+}
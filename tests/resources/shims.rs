@@ -0,0 +1,21 @@
+struct Guard(u32);
+
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+struct Pair(Guard, Guard);
+
+fn apply<F: Fn(u32) -> u32>(f: F, n: u32) -> u32 {
+    f(n)
+}
+
+fn double(n: u32) -> u32 {
+    2 * n
+}
+
+fn main() {
+    let pair = Pair(Guard(1), Guard(2));
+    let f: fn(u32) -> u32 = double;
+    assert_eq!(apply(f, pair.0 .0 + pair.1 .0), 6);
+}
@@ -0,0 +1,33 @@
+// The items of stable_ids_order_b.rs, in the opposite order.
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+enum Shape {
+    Circle(Point, u32),
+    Rect { corner: Point, size: (u16, u16) },
+}
+
+static ORIGIN: Point = Point { x: 0, y: 0 };
+
+fn area(shape: &Shape) -> i64 {
+    match shape {
+        Shape::Circle(center, r) => center.x as i64 + 3 * (*r as i64) * (*r as i64),
+        Shape::Rect { corner, size } => corner.y as i64 + size.0 as i64 * size.1 as i64,
+    }
+}
+
+fn shift(p: &Point, by: i32) -> Point {
+    Point { x: p.x + by, y: p.y - by }
+}
+
+fn main() {
+    let shapes = [
+        Shape::Circle(shift(&ORIGIN, 1), 2),
+        Shape::Rect { corner: shift(&ORIGIN, -1), size: (3, 4) },
+    ];
+    let total: i64 = shapes.iter().map(area).sum();
+    assert!(total > 0, "empty shapes");
+}
@@ -0,0 +1,15 @@
+union Word {
+    ptr: &'static u64,
+    addr: usize,
+}
+
+// a pointer whose pointee type cannot be recovered from the union
+const ANSWER: Word = Word {
+    ptr: &0x1122_3344_5566_7788,
+};
+
+fn main() {
+    let word = ANSWER;
+    assert_ne!(unsafe { word.addr }, 0);
+    assert_eq!(unsafe { *word.ptr }, 0x1122_3344_5566_7788);
+}
@@ -0,0 +1,10 @@
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn main() {
+    let x = std::hint::black_box(200);
+    if add(x, 50) == 250 {
+        panic!("unexpected");
+    }
+}
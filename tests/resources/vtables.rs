@@ -0,0 +1,73 @@
+trait Shape {
+    fn area(&self) -> u32;
+    fn sides(&self) -> u32 {
+        0
+    }
+    fn unit() -> Self
+    where
+        Self: Sized;
+}
+
+trait Describe {
+    fn describe(&self) -> u32;
+}
+
+trait Labelled: Describe + Shape {
+    fn label(&self) -> u32;
+}
+
+struct Square(u32);
+
+struct Named {
+    name: String,
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+    fn sides(&self) -> u32 {
+        4
+    }
+    fn unit() -> Self {
+        Square(1)
+    }
+}
+
+impl Shape for Named {
+    fn area(&self) -> u32 {
+        self.name.len() as u32
+    }
+    fn unit() -> Self {
+        Named {
+            name: String::new(),
+        }
+    }
+}
+
+impl Describe for Square {
+    fn describe(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Labelled for Square {
+    fn label(&self) -> u32 {
+        self.describe() + self.sides()
+    }
+}
+
+fn total(shapes: &[&dyn Shape]) -> u32 {
+    shapes.iter().map(|s| s.area() + s.sides()).sum()
+}
+
+fn main() {
+    let named = Named {
+        name: String::from("abc"),
+    };
+    let square = Square(2);
+    let labelled: &dyn Labelled = &Square::unit();
+    let double: Box<dyn Fn(u32) -> u32> = Box::new(|x| 2 * x);
+    let sum = total(&[&square, &named]) + labelled.label() + double(3);
+    assert_eq!(sum, 4 + 4 + 3 + 0 + 1 + 4 + 6);
+}
@@ -0,0 +1,95 @@
+//! Items and `functions` entries carry their instance kind, and the shims
+//! the bodies call are always items. With `LINK_INST`, the instance kinds
+//! in the `functions` keys are structured and reproducible.
+
+mod common;
+
+use common::run_resource;
+use stable_mir_json_reader::{
+    FnSymType, InstanceKindKey, InstanceKindTag, MonoItemKind, SmirJson, TypeMetadata,
+};
+
+/// The instance kind of the function item whose symbol contains `needle`.
+fn item_kind(smir: &SmirJson, needle: &str) -> Option<InstanceKindTag> {
+    smir.items
+        .iter()
+        .find_map(|item| match &item.mono_item_kind {
+            MonoItemKind::MonoItemFn { instance_kind, .. } if item.symbol_name.contains(needle) => {
+                Some(*instance_kind)
+            }
+            _ => None,
+        })
+}
+
+/// Every shim in `functions` is an item of the same instance kind.
+fn check_called_shims(smir: &SmirJson) {
+    for (_, sym, tag) in &smir.functions {
+        let FnSymType::NormalSym(symbol) = sym else {
+            continue;
+        };
+        if matches!(tag, InstanceKindTag::Item | InstanceKindTag::Virtual) {
+            continue;
+        }
+        assert_eq!(item_kind(smir, symbol), Some(*tag), "{symbol}");
+    }
+}
+
+#[test]
+fn instance_kinds_are_recorded() {
+    let smir = run_resource("shims", &[], &[]);
+    assert_eq!(item_kind(&smir, "5shims4main"), Some(InstanceKindTag::Item));
+    assert_eq!(
+        item_kind(&smir, "drop_in_place$LT$shims..Pair$GT$"),
+        Some(InstanceKindTag::DropGlue)
+    );
+    // calling a function pointer through `Fn` goes through a shim
+    assert!(smir
+        .functions
+        .iter()
+        .any(|(_, _, tag)| *tag == InstanceKindTag::FnPtrShim));
+    assert!(smir.functions.iter().any(|(_, sym, tag)| {
+        matches!(sym, FnSymType::IntrinsicSym(_)) && *tag == InstanceKindTag::Intrinsic
+    }));
+    check_called_shims(&smir);
+}
+
+#[test]
+fn called_shims_survive_the_path_filter() {
+    let smir = run_resource("shims", &[], &["--smir-filter-path=main"]);
+    assert_eq!(item_kind(&smir, "5shims5apply"), None);
+    // `main` drops a `Pair`, whose drop glue drops each `Guard`
+    assert_eq!(
+        item_kind(&smir, "drop_in_place$LT$shims..Pair$GT$"),
+        Some(InstanceKindTag::DropGlue)
+    );
+    assert_eq!(
+        item_kind(&smir, "drop_in_place$LT$shims..Guard$GT$"),
+        Some(InstanceKindTag::DropGlue)
+    );
+    check_called_shims(&smir);
+}
+
+#[test]
+fn link_inst_keys_are_structured_and_reproducible() {
+    let env = ["LINK_INST", "STABLE_IDS"];
+    let smir = run_resource("shims", &env, &[]);
+    // both runs also write back to the same bytes
+    assert_eq!(smir, run_resource("shims", &env, &[]));
+
+    for (key, _, tag) in &smir.functions {
+        assert_eq!(key.instance_kind.as_ref().map(|k| k.tag()), Some(*tag));
+    }
+    // the drop glue of `Pair` refers to the dropped type
+    let pair = smir
+        .types
+        .iter()
+        .find_map(|(ty, metadata)| match metadata {
+            TypeMetadata::StructType { name, .. } if name == "Pair" => Some(*ty),
+            _ => None,
+        })
+        .expect("no type Pair");
+    assert!(smir.functions.iter().any(|(key, _, _)| matches!(
+        key.instance_kind,
+        Some(InstanceKindKey::DropGlue(_, Some(ty))) if ty == pair
+    )));
+}
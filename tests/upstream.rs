@@ -0,0 +1,140 @@
+//! `--smir-upstream` adds the bodies of upstream functions called by the
+//! crate to `items`, up to a depth and from a set of crates.
+
+mod common;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use common::{get_resource_path, run_driver_with_args};
+use stable_mir_json_reader::{MonoItemKind, SmirJson, UpstreamBodies};
+
+fn program() -> PathBuf {
+    get_resource_path(vec!["tests", "resources", "upstream_calls.rs"]).into()
+}
+
+fn run(test: &str, args: &[&str]) -> SmirJson {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("upstream")
+        .join(test);
+    let output = run_driver_with_args(&program(), &out_dir, &["STABLE_IDS"], args);
+    stable_mir_json_reader::load(output).unwrap()
+}
+
+/// Symbol names of the local crate's items.
+fn local_items(smir: &SmirJson) -> BTreeSet<&str> {
+    smir.items
+        .iter()
+        .filter(|item| item.external_crate.is_none())
+        .map(|item| item.symbol_name.as_str())
+        .collect()
+}
+
+/// Names of the upstream items with their crates.
+fn upstream_items(smir: &SmirJson) -> BTreeSet<(&str, &str)> {
+    smir.items
+        .iter()
+        .filter_map(|item| {
+            let krate = item.external_crate.as_deref()?;
+            let MonoItemKind::MonoItemFn { name, body, .. } = &item.mono_item_kind else {
+                panic!("upstream item {} is not a function", item.symbol_name);
+            };
+            assert!(body.is_some(), "upstream item {name} has no body");
+            Some((name.as_str(), krate))
+        })
+        .collect()
+}
+
+#[test]
+fn upstream_bodies_only_with_flag() {
+    let plain = run("plain", &[]);
+    assert!(plain.header.flags.upstream.is_none());
+    assert!(upstream_items(&plain).is_empty());
+
+    let all = run("all", &["--smir-upstream"]);
+    assert_eq!(all.header.flags.upstream, Some(UpstreamBodies::default()));
+    // The local mono items are unaffected.
+    assert_eq!(local_items(&all), local_items(&plain));
+
+    let upstream = upstream_items(&all);
+    assert!(upstream.contains(&("core::panicking::panic_nounwind", "core")));
+    assert!(upstream.contains(&("std::alloc::handle_alloc_error", "alloc")));
+    // Called from `panic_nounwind` only, so at depth 2.
+    assert!(upstream.contains(&("core::panicking::panic_nounwind_fmt", "core")));
+}
+
+#[test]
+fn depth_and_crates() {
+    let all = run("all", &["--smir-upstream"]);
+    let all = upstream_items(&all);
+
+    let shallow = run("depth", &["--smir-upstream-depth=1"]);
+    assert_eq!(
+        shallow.header.flags.upstream,
+        Some(UpstreamBodies {
+            depth: Some(1),
+            crates: vec![],
+        })
+    );
+    let shallow = upstream_items(&shallow);
+    assert!(shallow.is_subset(&all));
+    assert!(shallow.contains(&("core::panicking::panic_nounwind", "core")));
+    assert!(!shallow.contains(&("core::panicking::panic_nounwind_fmt", "core")));
+
+    let core = run("crate", &["--smir-upstream-crate=core"]);
+    let core = upstream_items(&core);
+    assert!(core.contains(&("core::panicking::panic_nounwind_fmt", "core")));
+    assert_eq!(
+        core,
+        all.iter()
+            .copied()
+            .filter(|(_, krate)| *krate == "core")
+            .collect()
+    );
+
+    let none = run("depth0", &["--smir-upstream-depth=0"]);
+    assert!(upstream_items(&none).is_empty());
+}
+
+#[test]
+fn filtered_local_items_are_not_added() {
+    // `main` calls the local `begin_panic::<&str>` mono item, which the
+    // filter drops; it must not come back as an upstream body.
+    let smir = run("filtered", &["--smir-upstream", "--smir-filter-path=main"]);
+    let names = smir
+        .items
+        .iter()
+        .map(|item| match &item.mono_item_kind {
+            MonoItemKind::MonoItemFn { name, .. } => name.as_str(),
+            _ => item.symbol_name.as_str(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["main"]);
+}
+
+#[test]
+fn path_filter_applies_to_callees() {
+    // `exchange_malloc` calls the upstream `handle_alloc_error`, which has
+    // MIR but does not match the filter.
+    let filtered = run(
+        "filtered-callees",
+        &[
+            "--smir-upstream",
+            "--smir-filter-path=alloc::alloc::exchange_malloc",
+        ],
+    );
+    assert!(upstream_items(&filtered).is_empty());
+
+    let matching = run(
+        "matching-callees",
+        &[
+            "--smir-upstream",
+            "--smir-filter-path=alloc::alloc::exchange_malloc",
+            "--smir-filter-path=std::alloc::handle_alloc_error",
+        ],
+    );
+    assert_eq!(
+        upstream_items(&matching),
+        BTreeSet::from([("std::alloc::handle_alloc_error", "alloc")])
+    );
+}
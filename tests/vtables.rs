@@ -0,0 +1,151 @@
+//! Vtables list the functions in their slots, linked to `functions` and items.
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::run_resource;
+use stable_mir_json_reader::{
+    FnSymType, InstanceKindKey, SmirJson, TypeMetadata, VTableEntry, VTableInfo,
+};
+
+fn vtable<'a>(smir: &'a SmirJson, type_name: &str, trait_name: &str) -> &'a VTableInfo {
+    let ty = smir
+        .types
+        .iter()
+        .find_map(|(ty, metadata)| match metadata {
+            TypeMetadata::StructType { name, .. } if name == type_name => Some(*ty),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no type {type_name}"));
+    smir.vtables
+        .iter()
+        .find(|v| v.ty == ty && v.trait_name.as_deref() == Some(trait_name))
+        .unwrap_or_else(|| panic!("no vtable of {type_name} as {trait_name}"))
+}
+
+fn symbol(entry: &VTableEntry) -> Option<&str> {
+    let f = match entry {
+        VTableEntry::Method(f) | VTableEntry::MetadataDropInPlace(Some(f)) => f,
+        _ => return None,
+    };
+    match &f.symbol {
+        FnSymType::NormalSym(symbol) => Some(symbol),
+        _ => None,
+    }
+}
+
+/// The vtable of the closure in `main` as `dyn Fn(u32) -> u32`.
+fn closure_vtable(smir: &SmirJson) -> &VTableInfo {
+    smir.vtables
+        .iter()
+        .find(|v| {
+            v.trait_name.as_deref() == Some("std::ops::Fn")
+                && v.entries
+                    .iter()
+                    .filter_map(symbol)
+                    .any(|s| s.contains("vtables4main"))
+        })
+        .expect("no vtable for the closure")
+}
+
+/// Every function in a slot is an item, and keyed ones are in `functions`.
+fn check_slot_functions(smir: &SmirJson) {
+    let items = smir
+        .items
+        .iter()
+        .map(|item| item.symbol_name.as_str())
+        .collect::<HashSet<_>>();
+    for entry in smir.vtables.iter().flat_map(|v| &v.entries) {
+        let (VTableEntry::Method(f) | VTableEntry::MetadataDropInPlace(Some(f))) = entry else {
+            continue;
+        };
+        if let Some(symbol) = symbol(entry) {
+            assert!(items.contains(symbol), "{symbol} is not an item");
+        }
+        if let Some(key) = &f.key {
+            assert!(
+                smir.functions
+                    .iter()
+                    .any(|(k, symbol, _)| k == key && *symbol == f.symbol),
+                "{key:?} not in functions"
+            );
+        }
+    }
+}
+
+#[test]
+fn vtable_slots_are_linked_to_functions() {
+    let smir = run_resource("vtables", &[], &["--edition=2021"]);
+
+    // the header slots, then the methods in declaration order; the default
+    // method `sides` is the trait's own, and `unit` (`Self: Sized`) is absent
+    let named = vtable(&smir, "Named", "Shape");
+    assert_eq!((named.size, named.align), (24, 8));
+    assert!(matches!(
+        named.entries.as_slice(),
+        [
+            VTableEntry::MetadataDropInPlace(Some(_)),
+            VTableEntry::MetadataSize,
+            VTableEntry::MetadataAlign,
+            VTableEntry::Method(_),
+            VTableEntry::Method(_),
+        ]
+    ));
+    assert!(symbol(&named.entries[0]).unwrap().contains("drop_in_place"));
+    assert!(symbol(&named.entries[3])
+        .unwrap()
+        .contains("Named$u20$as$u20$vtables..Shape"));
+    assert!(symbol(&named.entries[4]).unwrap().contains("5Shape5sides"));
+
+    // a subtrait's vtable has the supertraits' methods first, and a pointer
+    // to the vtable of the second supertrait
+    let square_shape = vtable(&smir, "Square", "Shape");
+    let labelled = vtable(&smir, "Square", "Labelled");
+    assert_eq!((labelled.size, labelled.align), (4, 4));
+    assert!(matches!(
+        labelled.entries[0],
+        VTableEntry::MetadataDropInPlace(None)
+    ));
+    let methods = labelled.entries[3..]
+        .iter()
+        .map(|e| symbol(e).map(|s| s.split("17h").next().unwrap().to_string()))
+        .collect::<Vec<_>>();
+    assert!(methods[0].as_ref().unwrap().ends_with("8describe"));
+    assert!(methods[1].as_ref().unwrap().ends_with("4area"));
+    assert!(methods[2].as_ref().unwrap().ends_with("5sides"));
+    assert_eq!(
+        labelled.entries[6],
+        VTableEntry::TraitVPtr(square_shape.trait_ref.clone().unwrap())
+    );
+    assert!(methods[4].as_ref().unwrap().ends_with("5label"));
+
+    // a closure's `call` and `call_mut` are its body, which has no key,
+    // and without `LINK_INST` neither has the `call_once` vtable shim
+    let closure_fn = closure_vtable(&smir);
+    let VTableEntry::Method(call) = &closure_fn.entries[5] else {
+        panic!("expected `call`, got {:?}", closure_fn.entries[5]);
+    };
+    assert!(call.key.is_none());
+    let VTableEntry::Method(call_once) = &closure_fn.entries[3] else {
+        panic!("expected `call_once`, got {:?}", closure_fn.entries[3]);
+    };
+    assert!(call_once.key.is_none());
+
+    check_slot_functions(&smir);
+}
+
+#[test]
+fn vtable_shims_are_keyed_with_link_inst() {
+    let smir = run_resource("vtables", &["LINK_INST"], &["--edition=2021"]);
+    let closure_fn = closure_vtable(&smir);
+    let VTableEntry::Method(call_once) = &closure_fn.entries[3] else {
+        panic!("expected `call_once`, got {:?}", closure_fn.entries[3]);
+    };
+    let key = call_once.key.as_ref().expect("no key for the vtable shim");
+    assert!(matches!(
+        key.instance_kind,
+        Some(InstanceKindKey::VTableShim(_))
+    ));
+    check_slot_functions(&smir);
+}